@@ -28,7 +28,7 @@ fn collect_yield_raw() {
     let token_admin = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let pool_addr = env.register_contract_wasm(&None, pool::WASM);
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
     let pool_client = pool::Client::new(&env, &pool_addr);
 
     let receiver = env.register_contract(None, FlashLoanReceiverContract);
@@ -36,7 +36,6 @@ fn collect_yield_raw() {
 
     // Initialize the flash loan receiver contract.
     receiver_client.init(&token_id, &pool_addr, &(100 * STROOP));
-    pool_client.initialize(&token_id);
 
     token_admin.mint(&receiver, &(1000 * STROOP));
     token_admin.mint(&user1, &(100 * STROOP));
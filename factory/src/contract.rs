@@ -1,7 +1,8 @@
-use soroban_sdk::{contract, contractimpl, Address, BytesN, Env};
+use soroban_sdk::{contract, contractimpl, token, unwrap::UnwrapOptimized, Address, Bytes, BytesN, Env, Map, Vec};
 
-use crate::types::{Error, DataKey};
-use crate::{pool, storage::*, events};
+use crate::receiver::MultiReceiverClient;
+use crate::types::{Error, DataKey, LoanLimits, MaxLoan, PoolInfo, PoolRelease, PoolStatus, Role};
+use crate::{pool, storage::*, events, DEFAULT_FEE_TIER, FEE_TIERS, PAUSE_SYNC_LIMIT};
 
 #[contract]
 pub struct XycloansFactory;
@@ -14,76 +15,799 @@ pub trait PluggableInterface {
     ///
     /// [`set_pool()`] must be provided with:
     /// [`token_address: Address`] Address of the token used by the vault.
-    /// [`pool_address: Address`] Address of the vault contract.
-    fn set_pool(env: Env, token_address: Address, pool_address: Address) -> Result<(), Error>;
+    /// [`tier: u32`] Fee tier the vault is plugged in for.
+    /// [`pool_address: Address`] Address of the vault contract, its `token()` must be `token_address`.
+    /// [`replace: bool`] Whether an already plugged vault for the same token and tier can be overwritten.
+    fn set_pool(env: Env, token_address: Address, tier: u32, pool_address: Address, replace: bool) -> Result<(), Error>;
 }
 
 pub trait AdminInterface {
-    /// Constructor function, only to be callable once
+    /// Constructor function, run once when the factory is deployed
 
-    /// [`initialize()`] must be provided with:
+    /// [`__constructor()`] must be provided with:
     /// [`admin: Address`] Address of the proxy's admin
     /// [`pool_hash: BytesN<32>`] Hash of the pool
 
     /// The proxy's admin will only be able to plug in and out pools from the protocol
     /// without having any control over the deposited funds.
-    fn initialize(env: Env, admin: Address, pool_hash: BytesN<32>) -> Result<(), Error>;
+    fn __constructor(env: Env, admin: Address, pool_hash: BytesN<32>);
 
-    /// Deploys a pool.
-    fn deploy_pool(env: Env, token_address: Address, salt: BytesN<32>) -> Result<Address, Error>;
+    /// First step of the admin transfer, proposes `new_admin` as the next admin.
+    /// Proposing again overwrites the previous proposal.
+    fn propose_admin(env: Env, new_admin: Address) -> Result<(), Error>;
+
+    /// Second step of the admin transfer, the proposed admin accepts the role.
+    fn accept_admin(env: Env) -> Result<(), Error>;
+
+    /// Assigns `role` to `addr`, every role can be rotated independently of the others.
+    fn set_role(env: Env, role: Role, addr: Address) -> Result<(), Error>;
+
+    /// Releases a new pool WASM hash as `version`, which must be greater than the latest version.
+    /// Pools deployed from now on use the new hash, already deployed pools are left untouched.
+    /// Must be authorized by the [`Role::HashManager`].
+    fn set_pool_hash(env: Env, new_hash: BytesN<32>, version: u32) -> Result<(), Error>;
+
+    /// Moves the pool of `token_address` for `tier` through its lifecycle, the status is also pushed to the pool.
+    /// Deprecated pools stop accepting deposits, sunset pools also stop lending. Sunset is final.
+    fn set_pool_status(env: Env, token_address: Address, tier: u32, status: PoolStatus) -> Result<(), Error>;
+
+    /// Approves or revokes `distributor` to fund incentive programs paying its own token
+    /// to the liquidity providers of the pool of `token_address` for `tier`.
+    fn set_distributor(env: Env, token_address: Address, tier: u32, distributor: Address, approved: bool) -> Result<(), Error>;
+
+    /// Sets the limits on the loans of the pool of `token_address` for `tier`,
+    /// e.g. to cap the amounts lent out while a new token is being evaluated.
+    fn set_loan_limits(env: Env, token_address: Address, tier: u32, limits: LoanLimits) -> Result<(), Error>;
+
+    /// Toggles the permissionless mode, in which anyone can deploy the canonical pool of a token.
+    fn set_permissionless(env: Env, enabled: bool) -> Result<(), Error>;
+
+    /// Deploys a pool for `token_address` charging the fee of `tier`.
+    /// Must be authorized by the [`Role::Deployer`].
+    /// A token can have at most one pool per fee tier, see [`FEE_TIERS`] for the available tiers.
+    /// The default tier pool is the canonical pool of the token and must be deployed with the canonical salt,
+    /// i.e the sha256 of the token address XDR, see [`deploy_canonical_pool()`].
+    fn deploy_pool(env: Env, token_address: Address, tier: u32, salt: BytesN<32>) -> Result<Address, Error>;
+
+    /// Deploys a pool like [`deploy_pool()`] and seeds it with `amount` deposited by `seeder` in the same call,
+    /// so the pool never exists without liquidity. Must also be authorized by `seeder`.
+    fn deploy_pool_with_liquidity(env: Env, token_address: Address, tier: u32, salt: BytesN<32>, seeder: Address, amount: i128) -> Result<Address, Error>;
+
+    /// Deploys the canonical pool of `token_address`, i.e its default tier pool, with a salt derived from the token.
+    /// Must be authorized by the [`Role::Deployer`] unless the factory is in permissionless mode.
+    fn deploy_canonical_pool(env: Env, token_address: Address) -> Result<Address, Error>;
+}
+
+pub trait TokenLists {
+    /// Toggles the allowlist mode, in which only allowed tokens can get pools.
+    fn set_allowlist_mode(env: Env, enabled: bool) -> Result<(), Error>;
+
+    /// Toggles whether Stellar classic assets are allowed by default in allowlist mode,
+    /// see [`allow_stellar_asset()`].
+    fn set_sac_allowed_by_default(env: Env, enabled: bool) -> Result<(), Error>;
+
+    /// Adds or removes `token_address` from the allowlist.
+    fn set_allowed(env: Env, token_address: Address, allowed: bool) -> Result<(), Error>;
+
+    /// Adds or removes `token_address` from the blocklist. Blocked tokens can't get pools even if allowed.
+    fn set_blocked(env: Env, token_address: Address, blocked: bool) -> Result<(), Error>;
+
+    /// Allows the Stellar Asset Contract of `serialized_asset` (the XDR of the classic asset).
+    /// Can be called by anyone if Stellar classic assets are allowed by default, since the
+    /// contract address is derived from the asset and can't belong to any other contract.
+    /// Returns the address of the Stellar Asset Contract.
+    fn allow_stellar_asset(env: Env, serialized_asset: Bytes) -> Result<Address, Error>;
+
+    /// Returns whether the allowlist mode is enabled.
+    fn allowlist_mode(env: Env) -> bool;
+
+    /// Returns whether Stellar classic assets are allowed by default.
+    fn sac_allowed_by_default(env: Env) -> bool;
+
+    /// Returns whether `token_address` is in the allowlist.
+    fn is_allowed(env: Env, token_address: Address) -> bool;
+
+    /// Returns whether `token_address` is in the blocklist.
+    fn is_blocked(env: Env, token_address: Address) -> bool;
+
+    /// Returns whether a pool can currently be deployed for `token_address`.
+    fn can_deploy(env: Env, token_address: Address) -> bool;
+}
+
+pub trait Emergency {
+    /// Pauses borrows and deposits on the pools of `token_address`, or on every pool if no token is given.
+    /// Withdrawals stay open. Only callable by the guardian.
+    /// A token's pause is pushed down to its pools right away. The global pause stops the loans and
+    /// deposits routed through the factory right away and is pushed down to the first `PAUSE_SYNC_LIMIT`
+    /// pools of the registry, the others are reached through `sync_paused()`.
+    /// Returns the registry index `sync_paused()` must continue from, i.e `pool_count()` once every pool is synced.
+    fn pause(env: Env, token_address: Option<Address>) -> Result<u32, Error>;
+
+    /// Lifts a pause set with `pause()`, which it mirrors. Pools stay paused while either the global
+    /// or their token's pause is set. Only callable by the guardian.
+    fn unpause(env: Env, token_address: Option<Address>) -> Result<u32, Error>;
+
+    /// Pushes the pause state down to the pools of the registry from index `start`, at most `limit` of them.
+    /// Pools can't consult the factory on their own since the calls routed through the factory would
+    /// re-enter it, and the registry can be too large to be synced in a single call.
+    /// Anyone can call this since it only mirrors the factory's state.
+    /// Returns the registry index to continue from, i.e `pool_count()` once every pool is synced.
+    fn sync_paused(env: Env, start: u32, limit: u32) -> u32;
+
+    /// Returns whether the whole protocol is paused.
+    fn paused(env: Env) -> bool;
+
+    /// Returns whether the pools of `token_address` are paused, either globally or for the token.
+    fn is_paused(env: Env, token_address: Address) -> bool;
+}
+
+pub trait MultiFlashLoan {
+    /// Borrows multiple assets atomically, each from the factory's pool of that token in the given fee tier.
+
+    /// [`flash_loan_multi()`] must be provided with:
+    /// [`initiator: Address`] Address initiating the batch, must authorize the call.
+    /// [`receiver: Address`] Receiver contract implementing `exec_op_multi()`.
+    /// [`loans: Vec<(Address, u32, i128)>`] The (token, tier, amount) of every loan, a token can only appear once.
+    /// [`data: Bytes`] Arbitrary data forwarded to the receiver.
+
+    /// All assets are transferred to the receiver before it is invoked once with the
+    /// (token, amount, fee) of every loan. If any of the loans isn't repaid the whole batch is reverted.
+    fn flash_loan_multi(env: Env, initiator: Address, receiver: Address, loans: Vec<(Address, u32, i128)>, data: Bytes) -> Result<(), Error>;
+}
+
+pub trait Router {
+    /// Routes a flash loan to the pool of `token_address` in `tier`, see the pool's `borrow()`.
+    fn borrow(env: Env, token_address: Address, tier: u32, receiver_id: Address, amount: i128) -> Result<(), Error>;
+
+    /// Routes a deposit to the pool of `token_address` in `tier`, see the pool's `deposit()`.
+    /// `from` authorizes the whole invocation tree, which includes the pool's `deposit()`.
+    fn deposit(env: Env, token_address: Address, tier: u32, from: Address, amount: i128) -> Result<(), Error>;
+
+    /// Routes a withdrawal to the pool of `token_address` in `tier`, see the pool's `withdraw()`.
+    fn withdraw(env: Env, token_address: Address, tier: u32, addr: Address, amount: i128) -> Result<i128, Error>;
+
+    /// Routes a fee rewards update to the pool of `token_address` in `tier`, see the pool's `update_fee_rewards()`.
+    fn update_fee_rewards(env: Env, token_address: Address, tier: u32, addr: Address) -> Result<(), Error>;
+
+    /// Routes a matured fees withdrawal to the pool of `token_address` in `tier`, see the pool's `withdraw_matured()`.
+    fn withdraw_matured(env: Env, token_address: Address, tier: u32, addr: Address) -> Result<i128, Error>;
+
+    /// Updates the fee rewards of `addr` and withdraws its matured fees in the pools of every tier of every token in `tokens`.
+    /// Pools where `addr` has no matured fees are skipped.
+    /// Returns the (token, amount paid across its tiers) for every token in `tokens`.
+    fn claim_all(env: Env, addr: Address, tokens: Vec<Address>) -> Result<Vec<(Address, i128)>, Error>;
 }
 
 pub trait Common {
-    /// Reads from the storage the pool contract for a given token
+    /// Returns the factory's admin.
+    fn admin(env: Env) -> Result<Address, Error>;
+
+    /// Returns the address holding `role`.
+    fn role(env: Env, role: Role) -> Result<Address, Error>;
+
+    /// Returns whether anyone can deploy canonical pools.
+    fn permissionless(env: Env) -> bool;
+
+    /// Returns the address of the canonical pool of `token_address`, which is its
+    /// registered default tier pool if there is one, else the address it will be deployed at.
+    fn predict_pool_address(env: Env, token_address: Address) -> Address;
+
+    /// Returns the latest pool version.
+    fn latest_version(env: Env) -> u32;
+
+    /// Returns the pool version a registered pool was deployed with.
+    fn pool_version(env: Env, pool_address: Address) -> Result<u32, Error>;
+
+    /// Returns every pool WASM hash release, the initial hash being version 1.
+    fn pool_releases(env: Env) -> Vec<PoolRelease>;
+
+    /// Reads from the storage the active pool contract for a given token.
+    /// This is the default tier pool if it's active, else the first active pool deployed for the token.
     fn get_pool_address(env: Env, token_address: Address) -> Result<Address, Error>;
+
+    /// Same as [`get_pool_address()`], but deprecated and sunset pools are included if `include_inactive` is set.
+    fn find_pool(env: Env, token_address: Address, include_inactive: bool) -> Result<Address, Error>;
+
+    /// Returns the lifecycle status of a registered pool.
+    fn pool_status(env: Env, pool_address: Address) -> Result<PoolStatus, Error>;
+
+    /// Reads from the storage the pool contract for a given token and fee tier
+    fn get_tier_pool_address(env: Env, token_address: Address, tier: u32) -> Result<Address, Error>;
+
+    /// Returns the (tier, pool) of every pool of a given token
+    fn get_pools(env: Env, token_address: Address) -> Vec<(u32, Address)>;
+
+    /// Returns the number of pools in the registry.
+    fn pool_count(env: Env) -> u32;
+
+    /// Returns at most `limit` registry entries starting from the `start` index.
+    /// Entries are ordered by the time their (token, tier) was first registered.
+    fn list_pools(env: Env, start: u32, limit: u32) -> Vec<PoolInfo>;
+
+    /// Reads from the storage the token of a given pool contract
+    fn token_of(env: Env, pool_address: Address) -> Result<Address, Error>;
 }
 
 #[contractimpl]
 impl AdminInterface for XycloansFactory {
-    fn initialize(env: Env, admin: Address, pool_hash: BytesN<32>) -> Result<(), Error> {
-        if has_admin(&env) {
-            return Err(Error::AlreadyInitialized);
+    fn __constructor(env: Env, admin: Address, pool_hash: BytesN<32>) {
+        set_admin(&env, admin);
+        add_pool_release(&env, 1, &pool_hash);
+    }
+
+    fn propose_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        read_admin(&env)?.require_auth();
+
+        set_pending_admin(&env, new_admin.clone());
+        events::admin_proposed(&env, &new_admin);
+
+        Ok(())
+    }
+
+    fn accept_admin(env: Env) -> Result<(), Error> {
+        let new_admin = read_pending_admin(&env)?;
+        new_admin.require_auth();
+
+        set_admin(&env, new_admin.clone());
+        remove_pending_admin(&env);
+        events::admin_accepted(&env, &new_admin);
+
+        Ok(())
+    }
+
+    fn set_role(env: Env, role: Role, addr: Address) -> Result<(), Error> {
+        read_admin(&env)?.require_auth();
+
+        set_role(&env, role, addr.clone());
+        events::role_set(&env, role, &addr);
+
+        Ok(())
+    }
+
+    fn set_pool_hash(env: Env, new_hash: BytesN<32>, version: u32) -> Result<(), Error> {
+        read_role(&env, Role::HashManager)?.require_auth();
+
+        if version <= read_latest_version(&env) {
+            return Err(Error::InvalidVersion);
         }
 
-        set_admin(&env, admin);
-        write_pool_hash(&env, &pool_hash);
+        add_pool_release(&env, version, &new_hash);
+        events::pool_hash_set(&env, version, &new_hash);
 
         Ok(())
     }
 
-    fn deploy_pool(env: Env, token_address: Address, salt: BytesN<32>) -> Result<Address, Error> {
+    fn set_pool_status(env: Env, token_address: Address, tier: u32, status: PoolStatus) -> Result<(), Error> {
         read_admin(&env)?.require_auth();
 
-        let key = &DataKey::Pool(token_address.clone());
-        if env.storage().persistent().has(key) {
-            return Err(Error::PoolExists)
+        let pool_address = read_tier_pool(&env, token_address, tier)?;
+        if read_pool_status(&env, pool_address.clone()) == PoolStatus::Sunset {
+            return Err(Error::InvalidStatus);
         }
 
-        let pool_address = env.deployer().with_current_contract(salt).deploy(read_pool_hash(&env));
+        write_pool_status(&env, pool_address.clone(), status);
+
+        // plugged pools which aren't managed by the factory only have their status tracked here.
+        let pool_status = match status {
+            PoolStatus::Active => pool::PoolStatus::Active,
+            PoolStatus::Deprecated => pool::PoolStatus::Deprecated,
+            PoolStatus::Sunset => pool::PoolStatus::Sunset,
+        };
+        let _ = pool::Client::new(&env, &pool_address).try_set_status(&pool_status);
+
+        events::pool_status_set(&env, &pool_address, status);
+
+        Ok(())
+    }
+
+    fn set_distributor(env: Env, token_address: Address, tier: u32, distributor: Address, approved: bool) -> Result<(), Error> {
+        read_admin(&env)?.require_auth();
 
-        let pool = pool::Client::new(&env, &pool_address);
-        pool.initialize(&token_address);
+        let pool_address = read_tier_pool(&env, token_address, tier)?;
+        pool::Client::new(&env, &pool_address).set_distributor(&distributor, &approved);
+
+        Ok(())
+    }
+
+    fn set_loan_limits(env: Env, token_address: Address, tier: u32, limits: LoanLimits) -> Result<(), Error> {
+        read_admin(&env)?.require_auth();
+
+        let pool_address = read_tier_pool(&env, token_address, tier)?;
+        let max_loan = match limits.max_loan {
+            MaxLoan::Unlimited => pool::MaxLoan::Unlimited,
+            MaxLoan::Amount(max) => pool::MaxLoan::Amount(max),
+            MaxLoan::Share(share) => pool::MaxLoan::Share(share),
+        };
+        pool::Client::new(&env, &pool_address).set_loan_limits(&pool::LoanLimits {
+            min_loan: limits.min_loan,
+            max_loan,
+            max_volume_per_ledger: limits.max_volume_per_ledger,
+        });
+
+        Ok(())
+    }
+
+    fn set_permissionless(env: Env, enabled: bool) -> Result<(), Error> {
+        read_admin(&env)?.require_auth();
 
-        set_pool(&env, token_address, &pool_address);
-        events::deployed_pool(&env, &pool_address);
+        set_permissionless(&env, enabled);
+        events::permissionless_set(&env, enabled);
+
+        Ok(())
+    }
+
+    fn deploy_pool(env: Env, token_address: Address, tier: u32, salt: BytesN<32>) -> Result<Address, Error> {
+        read_role(&env, Role::Deployer)?.require_auth();
+
+        deploy(&env, token_address, tier, salt)
+    }
+
+    fn deploy_pool_with_liquidity(env: Env, token_address: Address, tier: u32, salt: BytesN<32>, seeder: Address, amount: i128) -> Result<Address, Error> {
+        read_role(&env, Role::Deployer)?.require_auth();
+        seeder.require_auth();
+
+        let pool_address = deploy(&env, token_address, tier, salt)?;
+        pool::Client::new(&env, &pool_address).deposit(&seeder, &amount);
 
         Ok(pool_address)
     }
+
+    fn deploy_canonical_pool(env: Env, token_address: Address) -> Result<Address, Error> {
+        if !is_permissionless(&env) {
+            read_role(&env, Role::Deployer)?.require_auth();
+        }
+
+        let salt = canonical_salt(&env, token_address.clone());
+        deploy(&env, token_address, DEFAULT_FEE_TIER, salt)
+    }
+}
+
+fn check_token_allowed(env: &Env, token_address: &Address) -> Result<(), Error> {
+    if is_blocked(env, token_address.clone()) {
+        return Err(Error::TokenNotAllowed)
+    }
+
+    if is_allowlist_mode(env) && !is_allowed(env, token_address.clone()) {
+        return Err(Error::TokenNotAllowed)
+    }
+
+    Ok(())
+}
+
+fn deploy(env: &Env, token_address: Address, tier: u32, salt: BytesN<32>) -> Result<Address, Error> {
+    if !FEE_TIERS.contains(&tier) {
+        return Err(Error::InvalidTier)
+    }
+
+    check_token_allowed(env, &token_address)?;
+
+    let key = &DataKey::Pool(token_address.clone(), tier);
+    if env.storage().persistent().has(key) {
+        return Err(Error::PoolExists)
+    }
+
+    // the default tier pool is always deployed at the canonical address.
+    if tier == DEFAULT_FEE_TIER && salt != canonical_salt(env, token_address.clone()) {
+        return Err(Error::InvalidSalt)
+    }
+
+    let pool_address = env.deployer().with_current_contract(salt).deploy_v2(
+        read_pool_hash(env),
+        (token_address.clone(), tier, Some(env.current_contract_address())),
+    );
+
+    set_pool(env, token_address.clone(), tier, &pool_address);
+    write_pool_version(env, pool_address.clone(), read_latest_version(env));
+
+    if is_pool_paused(env, token_address) {
+        pool::Client::new(env, &pool_address).set_paused(&true);
+    }
+
+    events::deployed_pool(env, &pool_address);
+
+    Ok(pool_address)
+}
+
+#[contractimpl]
+impl TokenLists for XycloansFactory {
+    fn set_allowlist_mode(env: Env, enabled: bool) -> Result<(), Error> {
+        read_admin(&env)?.require_auth();
+
+        set_allowlist_mode(&env, enabled);
+        events::allowlist_mode_set(&env, enabled);
+
+        Ok(())
+    }
+
+    fn set_sac_allowed_by_default(env: Env, enabled: bool) -> Result<(), Error> {
+        read_admin(&env)?.require_auth();
+
+        set_sac_allowed_by_default(&env, enabled);
+        events::sac_allowed_by_default_set(&env, enabled);
+
+        Ok(())
+    }
+
+    fn set_allowed(env: Env, token_address: Address, allowed: bool) -> Result<(), Error> {
+        read_admin(&env)?.require_auth();
+
+        set_allowed(&env, token_address.clone(), allowed);
+        events::allowed_set(&env, &token_address, allowed);
+
+        Ok(())
+    }
+
+    fn set_blocked(env: Env, token_address: Address, blocked: bool) -> Result<(), Error> {
+        read_admin(&env)?.require_auth();
+
+        set_blocked(&env, token_address.clone(), blocked);
+        events::blocked_set(&env, &token_address, blocked);
+
+        Ok(())
+    }
+
+    fn allow_stellar_asset(env: Env, serialized_asset: Bytes) -> Result<Address, Error> {
+        if !is_sac_allowed_by_default(&env) {
+            read_admin(&env)?.require_auth();
+        }
+
+        let token_address = stellar_asset_address(&env, serialized_asset);
+        set_allowed(&env, token_address.clone(), true);
+        events::allowed_set(&env, &token_address, true);
+
+        Ok(token_address)
+    }
+
+    fn allowlist_mode(env: Env) -> bool {
+        is_allowlist_mode(&env)
+    }
+
+    fn sac_allowed_by_default(env: Env) -> bool {
+        is_sac_allowed_by_default(&env)
+    }
+
+    fn is_allowed(env: Env, token_address: Address) -> bool {
+        is_allowed(&env, token_address)
+    }
+
+    fn is_blocked(env: Env, token_address: Address) -> bool {
+        is_blocked(&env, token_address)
+    }
+
+    fn can_deploy(env: Env, token_address: Address) -> bool {
+        check_token_allowed(&env, &token_address).is_ok()
+    }
+}
+
+#[contractimpl]
+impl Emergency for XycloansFactory {
+    fn pause(env: Env, token_address: Option<Address>) -> Result<u32, Error> {
+        read_role(&env, Role::Guardian)?.require_auth();
+
+        Ok(set_pause(&env, token_address, true))
+    }
+
+    fn unpause(env: Env, token_address: Option<Address>) -> Result<u32, Error> {
+        read_role(&env, Role::Guardian)?.require_auth();
+
+        Ok(set_pause(&env, token_address, false))
+    }
+
+    fn paused(env: Env) -> bool {
+        is_paused(&env)
+    }
+
+    fn is_paused(env: Env, token_address: Address) -> bool {
+        is_pool_paused(&env, token_address)
+    }
+
+    fn sync_paused(env: Env, start: u32, limit: u32) -> u32 {
+        sync_pools(&env, start, limit)
+    }
+}
+
+fn is_pool_paused(env: &Env, token_address: Address) -> bool {
+    is_paused(env) || is_token_paused(env, token_address)
+}
+
+fn check_not_paused(env: &Env, token_address: Address) -> Result<(), Error> {
+    if is_pool_paused(env, token_address) {
+        return Err(Error::Paused);
+    }
+
+    Ok(())
+}
+
+// the global pause is only pushed down to the first pools of the registry,
+// the others are synced through `sync_paused()`.
+fn set_pause(env: &Env, token_address: Option<Address>, paused: bool) -> u32 {
+    let synced = if let Some(token_address) = token_address.clone() {
+        set_token_paused(env, token_address.clone(), paused);
+        for tier in read_tiers(env, token_address.clone()).iter() {
+            push_paused(env, token_address.clone(), tier);
+        }
+
+        read_pool_count(env)
+    } else {
+        set_paused(env, paused);
+        sync_pools(env, 0, PAUSE_SYNC_LIMIT)
+    };
+
+    events::paused_set(env, &token_address, paused);
+    synced
+}
+
+// pushes the pause state to at most `limit` pools of the registry from `start`, returns where it stopped.
+fn sync_pools(env: &Env, start: u32, limit: u32) -> u32 {
+    let end = start.saturating_add(limit).min(read_pool_count(env));
+
+    for index in start..end {
+        let (token_address, tier) = read_pool_at(env, index);
+        push_paused(env, token_address, tier);
+    }
+
+    end
+}
+
+// plugged pools which aren't managed by the factory can't be paused.
+fn push_paused(env: &Env, token_address: Address, tier: u32) {
+    let paused = is_pool_paused(env, token_address.clone());
+    if let Ok(pool_address) = read_tier_pool(env, token_address, tier) {
+        let _ = pool::Client::new(env, &pool_address).try_set_paused(&paused);
+    }
+}
+
+#[contractimpl]
+impl MultiFlashLoan for XycloansFactory {
+    fn flash_loan_multi(env: Env, initiator: Address, receiver: Address, loans: Vec<(Address, u32, i128)>, data: Bytes) -> Result<(), Error> {
+        initiator.require_auth();
+
+        if loans.is_empty() {
+            return Err(Error::InvalidBatch);
+        }
+
+        // resolve the pools first, borrowing the same token twice is not allowed.
+        let mut pools: Map<Address, Address> = Map::new(&env);
+        for (token_address, tier, _) in loans.iter() {
+            if pools.contains_key(token_address.clone()) {
+                return Err(Error::InvalidBatch);
+            }
+
+            check_not_paused(&env, token_address.clone())?;
+            pools.set(token_address.clone(), read_tier_pool(&env, token_address, tier)?);
+        }
+
+        // lend every asset to the receiver.
+        let mut lent: Vec<(Address, i128, i128)> = Vec::new(&env);
+        for (token_address, _, amount) in loans.iter() {
+            let pool = pool::Client::new(&env, &pools.get_unchecked(token_address.clone()));
+            let fee = pool.lend(&receiver, &amount);
+            lent.push_back((token_address, amount, fee));
+        }
+
+        MultiReceiverClient::new(&env, &receiver).exec_op_multi(&initiator, &lent, &data);
+
+        // move (`amount` + fees) from the receiver back to each pool and let the pool verify the repayment.
+        for (token_address, amount, fee) in lent.iter() {
+            let pool_address = pools.get_unchecked(token_address.clone());
+            let repayment = amount.checked_add(fee).ok_or(Error::Overflow)?;
+            let res = token::Client::new(&env, &token_address).try_transfer_from(
+                &env.current_contract_address(),
+                &receiver,
+                &pool_address,
+                &repayment,
+            );
+
+            if let Ok(Ok(_)) = res {
+                pool::Client::new(&env, &pool_address).settle();
+            } else {
+                return Err(Error::LoanNotRepaid);
+            }
+        }
+
+        events::multi_loan(&env, &receiver, &lent);
+        Ok(())
+    }
+}
+
+// The pool requires the auth of the user again on its own functions, the user's
+// signature covers those sub-invocations as long as they authorize the full tree.
+// Pools are routed to whatever their status, each pool enforces what it still allows.
+#[contractimpl]
+impl Router for XycloansFactory {
+    fn borrow(env: Env, token_address: Address, tier: u32, receiver_id: Address, amount: i128) -> Result<(), Error> {
+        check_not_paused(&env, token_address.clone())?;
+
+        let pool = pool::Client::new(&env, &read_tier_pool(&env, token_address, tier)?);
+        pool.borrow(&receiver_id, &amount);
+
+        Ok(())
+    }
+
+    fn deposit(env: Env, token_address: Address, tier: u32, from: Address, amount: i128) -> Result<(), Error> {
+        from.require_auth();
+        check_not_paused(&env, token_address.clone())?;
+
+        let pool = pool::Client::new(&env, &read_tier_pool(&env, token_address, tier)?);
+        pool.deposit(&from, &amount);
+
+        Ok(())
+    }
+
+    fn withdraw(env: Env, token_address: Address, tier: u32, addr: Address, amount: i128) -> Result<i128, Error> {
+        addr.require_auth();
+
+        let pool = pool::Client::new(&env, &read_tier_pool(&env, token_address, tier)?);
+        Ok(pool.withdraw(&addr, &amount))
+    }
+
+    fn update_fee_rewards(env: Env, token_address: Address, tier: u32, addr: Address) -> Result<(), Error> {
+        let pool = pool::Client::new(&env, &read_tier_pool(&env, token_address, tier)?);
+        pool.update_fee_rewards(&addr);
+
+        Ok(())
+    }
+
+    fn withdraw_matured(env: Env, token_address: Address, tier: u32, addr: Address) -> Result<i128, Error> {
+        addr.require_auth();
+
+        let pool = pool::Client::new(&env, &read_tier_pool(&env, token_address, tier)?);
+        Ok(pool.withdraw_matured(&addr))
+    }
+
+    fn claim_all(env: Env, addr: Address, tokens: Vec<Address>) -> Result<Vec<(Address, i128)>, Error> {
+        addr.require_auth();
+
+        let mut payouts: Vec<(Address, i128)> = Vec::new(&env);
+        for token_address in tokens.iter() {
+            let tiers = read_tiers(&env, token_address.clone());
+            if tiers.is_empty() {
+                return Err(Error::NoPool);
+            }
+
+            let mut paid = 0;
+            for tier in tiers.iter() {
+                let pool = pool::Client::new(&env, &read_tier_pool(&env, token_address.clone(), tier)?);
+                pool.update_fee_rewards(&addr);
+
+                // withdrawing would fail with `NoFeesMatured`, skip the pool instead.
+                if pool.matured(&addr) > 0 {
+                    paid += pool.withdraw_matured(&addr);
+                }
+            }
+
+            payouts.push_back((token_address, paid));
+        }
+
+        Ok(payouts)
+    }
 }
 
 #[contractimpl]
 impl Common for XycloansFactory {
+    fn admin(env: Env) -> Result<Address, Error> {
+        read_admin(&env)
+    }
+
+    fn role(env: Env, role: Role) -> Result<Address, Error> {
+        read_role(&env, role)
+    }
+
+    fn permissionless(env: Env) -> bool {
+        is_permissionless(&env)
+    }
+
+    fn predict_pool_address(env: Env, token_address: Address) -> Address {
+        if let Ok(pool_address) = read_tier_pool(&env, token_address.clone(), DEFAULT_FEE_TIER) {
+            return pool_address;
+        }
+
+        let salt = canonical_salt(&env, token_address);
+        env.deployer().with_current_contract(salt).deployed_address()
+    }
+
+    fn latest_version(env: Env) -> u32 {
+        read_latest_version(&env)
+    }
+
+    fn pool_version(env: Env, pool_address: Address) -> Result<u32, Error> {
+        read_token_of(&env, pool_address.clone())?;
+        Ok(read_pool_version(&env, pool_address))
+    }
+
+    fn pool_releases(env: Env) -> Vec<PoolRelease> {
+        read_pool_releases(&env)
+    }
+
     fn get_pool_address(env: Env, token_address: Address) -> Result<Address, Error> {
-        read_pool(&env, token_address)
+        read_pool(&env, token_address, false)
+    }
+
+    fn find_pool(env: Env, token_address: Address, include_inactive: bool) -> Result<Address, Error> {
+        read_pool(&env, token_address, include_inactive)
+    }
+
+    fn pool_status(env: Env, pool_address: Address) -> Result<PoolStatus, Error> {
+        read_token_of(&env, pool_address.clone())?;
+        Ok(read_pool_status(&env, pool_address))
+    }
+
+    fn get_tier_pool_address(env: Env, token_address: Address, tier: u32) -> Result<Address, Error> {
+        read_tier_pool(&env, token_address, tier)
+    }
+
+    fn get_pools(env: Env, token_address: Address) -> Vec<(u32, Address)> {
+        let mut pools = Vec::new(&env);
+        for tier in read_tiers(&env, token_address.clone()).iter() {
+            pools.push_back((tier, read_tier_pool(&env, token_address.clone(), tier).unwrap_optimized()));
+        }
+
+        pools
+    }
+
+    fn pool_count(env: Env) -> u32 {
+        read_pool_count(&env)
+    }
+
+    fn list_pools(env: Env, start: u32, limit: u32) -> Vec<PoolInfo> {
+        let end = start.saturating_add(limit).min(read_pool_count(&env));
+
+        let mut pools = Vec::new(&env);
+        for index in start..end {
+            let (token, tier) = read_pool_at(&env, index);
+            let pool = read_tier_pool(&env, token.clone(), tier).unwrap_optimized();
+
+            pools.push_back(PoolInfo {
+                version: read_pool_version(&env, pool.clone()),
+                status: read_pool_status(&env, pool.clone()),
+                token,
+                tier,
+                pool,
+            });
+        }
+
+        pools
+    }
+
+    fn token_of(env: Env, pool_address: Address) -> Result<Address, Error> {
+        read_token_of(&env, pool_address)
     }
 }
 
 #[cfg(feature = "pluggable")]
+#[contractimpl]
 impl PluggableInterface for XycloansFactory {
-    fn set_pool(env: Env, token_address: Address, pool_address: Address) -> Result<(), Error> {
+    fn set_pool(env: Env, token_address: Address, tier: u32, pool_address: Address, replace: bool) -> Result<(), Error> {
         read_admin(&env)?.require_auth();
 
-        set_pool(&env, token_address, &pool_address);
+        if !FEE_TIERS.contains(&tier) {
+            return Err(Error::InvalidTier)
+        }
+
+        check_token_allowed(&env, &token_address)?;
+
+        // the plugged contract must be a pool for the same token.
+        match pool::Client::new(&env, &pool_address).try_token() {
+            Ok(Ok(pool_token)) if pool_token == token_address => {},
+            _ => return Err(Error::TokenMismatch),
+        }
+
+        let old_pool = read_tier_pool(&env, token_address.clone(), tier).ok();
+        if old_pool.is_some() && !replace {
+            return Err(Error::PoolExists)
+        }
+
+        set_pool(&env, token_address.clone(), tier, &pool_address);
+        if is_pool_paused(&env, token_address.clone()) {
+            let _ = pool::Client::new(&env, &pool_address).try_set_paused(&true);
+        }
+
+        if let Some(old_pool) = old_pool {
+            events::pool_replaced(&env, &token_address, &old_pool, &pool_address);
+        } else {
+            events::pool_plugged(&env, &token_address, &pool_address);
+        }
+
         Ok(())
     }
 }
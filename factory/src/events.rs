@@ -1,6 +1,80 @@
-use soroban_sdk::{Env, Address, symbol_short};
+use soroban_sdk::{Env, Address, BytesN, Vec, symbol_short};
+
+use crate::types::{PoolStatus, Role};
 
 pub(crate) fn deployed_pool(env: &Env, contract: &Address) {
     let topics = (symbol_short!("deployed"), );
     env.events().publish(topics, contract);
-}
\ No newline at end of file
+}
+
+pub(crate) fn multi_loan(env: &Env, receiver: &Address, loans: &Vec<(Address, i128, i128)>) {
+    let topics = (symbol_short!("multiloan"), receiver);
+    env.events().publish(topics, loans.clone());
+}
+
+pub(crate) fn admin_proposed(env: &Env, admin: &Address) {
+    let topics = (symbol_short!("propadmin"), );
+    env.events().publish(topics, admin);
+}
+
+pub(crate) fn admin_accepted(env: &Env, admin: &Address) {
+    let topics = (symbol_short!("newadmin"), );
+    env.events().publish(topics, admin);
+}
+
+pub(crate) fn role_set(env: &Env, role: Role, addr: &Address) {
+    let topics = (symbol_short!("setrole"), role);
+    env.events().publish(topics, addr);
+}
+
+pub(crate) fn pool_hash_set(env: &Env, version: u32, hash: &BytesN<32>) {
+    let topics = (symbol_short!("poolhash"), version);
+    env.events().publish(topics, hash.clone());
+}
+
+pub(crate) fn permissionless_set(env: &Env, enabled: bool) {
+    let topics = (symbol_short!("permless"), );
+    env.events().publish(topics, enabled);
+}
+
+#[cfg(feature = "pluggable")]
+pub(crate) fn pool_plugged(env: &Env, token: &Address, pool: &Address) {
+    let topics = (symbol_short!("plugged"), token);
+    env.events().publish(topics, pool);
+}
+
+#[cfg(feature = "pluggable")]
+pub(crate) fn pool_replaced(env: &Env, token: &Address, old_pool: &Address, pool: &Address) {
+    let topics = (symbol_short!("replaced"), token);
+    env.events().publish(topics, (old_pool.clone(), pool.clone()));
+}
+
+pub(crate) fn pool_status_set(env: &Env, pool: &Address, status: PoolStatus) {
+    let topics = (symbol_short!("status"), pool);
+    env.events().publish(topics, status);
+}
+
+pub(crate) fn allowlist_mode_set(env: &Env, enabled: bool) {
+    let topics = (symbol_short!("allowlist"), );
+    env.events().publish(topics, enabled);
+}
+
+pub(crate) fn sac_allowed_by_default_set(env: &Env, enabled: bool) {
+    let topics = (symbol_short!("sacallow"), );
+    env.events().publish(topics, enabled);
+}
+
+pub(crate) fn allowed_set(env: &Env, token: &Address, allowed: bool) {
+    let topics = (symbol_short!("allowed"), token);
+    env.events().publish(topics, allowed);
+}
+
+pub(crate) fn blocked_set(env: &Env, token: &Address, blocked: bool) {
+    let topics = (symbol_short!("blocked"), token);
+    env.events().publish(topics, blocked);
+}
+
+pub(crate) fn paused_set(env: &Env, token: &Option<Address>, paused: bool) {
+    let topics = (symbol_short!("paused"), token.clone());
+    env.events().publish(topics, paused);
+}
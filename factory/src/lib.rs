@@ -4,9 +4,22 @@ mod contract;
 mod storage;
 mod types;
 mod events;
+mod receiver;
+
+/// Fee tiers, in basis points, that pools can be deployed for.
+pub(crate) const FEE_TIERS: [u32; 3] = [3, 8, 30];
+
+/// Tier of the canonical pool of a token.
+pub(crate) const DEFAULT_FEE_TIER: u32 = 8;
+
+/// Pools of the registry synced right away by a global pause, see `sync_paused()` for the rest.
+pub(crate) const PAUSE_SYNC_LIMIT: u32 = 10;
 
 mod pool {
     use soroban_sdk::contractimport;
 
     contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
 }
+
+#[cfg(all(test, feature = "pluggable"))]
+mod test;
@@ -0,0 +1,10 @@
+use soroban_sdk::{contractclient, Address, Bytes, Env, Vec};
+
+/// Interface that receivers of batch flash loans issued through the factory must implement.
+#[contractclient(name = "MultiReceiverClient")]
+pub trait MultiFlashLoanReceiver {
+    /// Invoked once all the assets of the batch have been transferred to the receiver.
+    /// `loans` holds a (token, amount, fee) entry for every borrowed asset. Before returning
+    /// the receiver must approve the factory for `amount + fee` of every token.
+    fn exec_op_multi(env: Env, initiator: Address, loans: Vec<(Address, i128, i128)>, data: Bytes);
+}
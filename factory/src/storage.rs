@@ -1,6 +1,7 @@
-use soroban_sdk::{unwrap::UnwrapOptimized, Address, BytesN, Env};
+use soroban_sdk::{unwrap::UnwrapOptimized, xdr::ToXdr, Address, Bytes, BytesN, Env, Vec};
 
-use crate::types::{DataKey, Error};
+use crate::types::{DataKey, Error, PoolRelease, PoolStatus, Role};
+use crate::DEFAULT_FEE_TIER;
 
 pub(crate) fn set_admin(env: &Env, admin: Address) {
     env.storage().instance().set(&DataKey::Admin, &admin);
@@ -14,17 +15,58 @@ pub(crate) fn read_admin(env: &Env) -> Result<Address, Error> {
     }
 }
 
-pub(crate) fn has_admin(env: &Env) -> bool {
-    env.storage().instance().has(&DataKey::Admin)
+pub(crate) fn set_pending_admin(env: &Env, admin: Address) {
+    env.storage().instance().set(&DataKey::PendingAdmin, &admin);
 }
 
-pub(crate) fn set_pool(env: &Env, token_address: Address, pool_address: &Address) {
-    let key = &DataKey::Pool(token_address);
+pub(crate) fn read_pending_admin(env: &Env) -> Result<Address, Error> {
+    if let Some(admin_id) = env.storage().instance().get(&DataKey::PendingAdmin) {
+        Ok(admin_id)
+    } else {
+        Err(Error::NoPendingAdmin)
+    }
+}
+
+pub(crate) fn remove_pending_admin(env: &Env) {
+    env.storage().instance().remove(&DataKey::PendingAdmin);
+}
+
+pub(crate) fn set_role(env: &Env, role: Role, addr: Address) {
+    env.storage().instance().set(&DataKey::Role(role), &addr);
+}
+
+// roles that were never assigned are held by the admin.
+pub(crate) fn read_role(env: &Env, role: Role) -> Result<Address, Error> {
+    if let Some(addr) = env.storage().instance().get(&DataKey::Role(role)) {
+        Ok(addr)
+    } else {
+        read_admin(env)
+    }
+}
+
+// Also keeps the pool registry consistent: a new (token, tier) is appended to the registry,
+// while replacing the pool of a known (token, tier) only updates the reverse lookup.
+pub(crate) fn set_pool(env: &Env, token_address: Address, tier: u32, pool_address: &Address) {
+    if let Ok(old_pool) = read_tier_pool(env, token_address.clone(), tier) {
+        env.storage().persistent().remove(&DataKey::TokenOf(old_pool));
+    } else {
+        let count = read_pool_count(env);
+        env.storage().persistent().set(&DataKey::PoolAt(count), &(token_address.clone(), tier));
+        env.storage().instance().set(&DataKey::PoolCount, &(count + 1));
+
+        let mut tiers = read_tiers(env, token_address.clone());
+        tiers.push_back(tier);
+        env.storage().persistent().set(&DataKey::Tiers(token_address.clone()), &tiers);
+    }
+
+    env.storage().persistent().set(&DataKey::TokenOf(pool_address.clone()), &token_address);
+
+    let key = &DataKey::Pool(token_address, tier);
     env.storage().persistent().set(key, &pool_address);
 }
 
-pub(crate) fn read_pool(env: &Env, token_address: Address) -> Result<Address, Error> {
-    let key = &DataKey::Pool(token_address);
+pub(crate) fn read_tier_pool(env: &Env, token_address: Address, tier: u32) -> Result<Address, Error> {
+    let key = &DataKey::Pool(token_address, tier);
     if let Some(vault_address) = env.storage().persistent().get(key) {
         Ok(vault_address)
     } else {
@@ -32,6 +74,158 @@ pub(crate) fn read_pool(env: &Env, token_address: Address) -> Result<Address, Er
     }
 }
 
+// The default tier pool is the canonical pool of a token, tokens without one
+// resolve to the first tier that was deployed for them.
+// Unless `include_inactive` is set, deprecated and sunset pools are skipped.
+pub(crate) fn read_pool(env: &Env, token_address: Address, include_inactive: bool) -> Result<Address, Error> {
+    let is_eligible = |pool_address: &Address| {
+        include_inactive || read_pool_status(env, pool_address.clone()) == PoolStatus::Active
+    };
+
+    if let Ok(pool_address) = read_tier_pool(env, token_address.clone(), DEFAULT_FEE_TIER) {
+        if is_eligible(&pool_address) {
+            return Ok(pool_address);
+        }
+    }
+
+    for tier in read_tiers(env, token_address.clone()).iter() {
+        let pool_address = read_tier_pool(env, token_address.clone(), tier)?;
+        if is_eligible(&pool_address) {
+            return Ok(pool_address);
+        }
+    }
+
+    Err(Error::NoPool)
+}
+
+pub(crate) fn read_tiers(env: &Env, token_address: Address) -> Vec<u32> {
+    let key = &DataKey::Tiers(token_address);
+    env.storage().persistent().get(key).unwrap_or(Vec::new(env))
+}
+
+pub(crate) fn set_permissionless(env: &Env, enabled: bool) {
+    env.storage().instance().set(&DataKey::Permissionless, &enabled);
+}
+
+pub(crate) fn is_permissionless(env: &Env) -> bool {
+    env.storage().instance().get(&DataKey::Permissionless).unwrap_or(false)
+}
+
+// the salt of the canonical pool of a token only depends on the token, so its address can be predicted.
+pub(crate) fn canonical_salt(env: &Env, token_address: Address) -> BytesN<32> {
+    env.crypto().sha256(&token_address.to_xdr(env)).to_bytes()
+}
+
+pub(crate) fn set_allowlist_mode(env: &Env, enabled: bool) {
+    env.storage().instance().set(&DataKey::AllowlistMode, &enabled);
+}
+
+pub(crate) fn is_allowlist_mode(env: &Env) -> bool {
+    env.storage().instance().get(&DataKey::AllowlistMode).unwrap_or(false)
+}
+
+pub(crate) fn set_sac_allowed_by_default(env: &Env, enabled: bool) {
+    env.storage().instance().set(&DataKey::SacAllowedByDefault, &enabled);
+}
+
+pub(crate) fn is_sac_allowed_by_default(env: &Env) -> bool {
+    env.storage().instance().get(&DataKey::SacAllowedByDefault).unwrap_or(false)
+}
+
+pub(crate) fn set_allowed(env: &Env, token_address: Address, allowed: bool) {
+    let key = &DataKey::Allowed(token_address);
+    if allowed {
+        env.storage().persistent().set(key, &true);
+    } else {
+        env.storage().persistent().remove(key);
+    }
+}
+
+pub(crate) fn is_allowed(env: &Env, token_address: Address) -> bool {
+    env.storage().persistent().has(&DataKey::Allowed(token_address))
+}
+
+pub(crate) fn set_blocked(env: &Env, token_address: Address, blocked: bool) {
+    let key = &DataKey::Blocked(token_address);
+    if blocked {
+        env.storage().persistent().set(key, &true);
+    } else {
+        env.storage().persistent().remove(key);
+    }
+}
+
+pub(crate) fn is_blocked(env: &Env, token_address: Address) -> bool {
+    env.storage().persistent().has(&DataKey::Blocked(token_address))
+}
+
+pub(crate) fn set_paused(env: &Env, paused: bool) {
+    env.storage().instance().set(&DataKey::Paused, &paused);
+}
+
+pub(crate) fn is_paused(env: &Env) -> bool {
+    env.storage().instance().get(&DataKey::Paused).unwrap_or(false)
+}
+
+pub(crate) fn set_token_paused(env: &Env, token_address: Address, paused: bool) {
+    let key = &DataKey::TokenPaused(token_address);
+    if paused {
+        env.storage().persistent().set(key, &true);
+    } else {
+        env.storage().persistent().remove(key);
+    }
+}
+
+pub(crate) fn is_token_paused(env: &Env, token_address: Address) -> bool {
+    env.storage().persistent().has(&DataKey::TokenPaused(token_address))
+}
+
+// the address of a Stellar Asset Contract is derived from the asset alone,
+// so no other contract can live at this address.
+pub(crate) fn stellar_asset_address(env: &Env, serialized_asset: Bytes) -> Address {
+    env.deployer().with_stellar_asset(serialized_asset).deployed_address()
+}
+
+pub(crate) fn read_pool_count(env: &Env) -> u32 {
+    env.storage().instance().get(&DataKey::PoolCount).unwrap_or(0)
+}
+
+pub(crate) fn read_pool_at(env: &Env, index: u32) -> (Address, u32) {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PoolAt(index))
+        .unwrap_optimized()
+}
+
+pub(crate) fn read_token_of(env: &Env, pool_address: Address) -> Result<Address, Error> {
+    let key = &DataKey::TokenOf(pool_address);
+    if let Some(token_address) = env.storage().persistent().get(key) {
+        Ok(token_address)
+    } else {
+        Err(Error::NoPool)
+    }
+}
+
+pub(crate) fn write_pool_version(env: &Env, pool_address: Address, version: u32) {
+    let key = &DataKey::PoolVersion(pool_address);
+    env.storage().persistent().set(key, &version);
+}
+
+// pools which weren't deployed by the factory report the first version.
+pub(crate) fn read_pool_version(env: &Env, pool_address: Address) -> u32 {
+    let key = &DataKey::PoolVersion(pool_address);
+    env.storage().persistent().get(key).unwrap_or(1)
+}
+
+pub(crate) fn write_pool_status(env: &Env, pool_address: Address, status: PoolStatus) {
+    let key = &DataKey::PoolStatus(pool_address);
+    env.storage().persistent().set(key, &status);
+}
+
+pub(crate) fn read_pool_status(env: &Env, pool_address: Address) -> PoolStatus {
+    let key = &DataKey::PoolStatus(pool_address);
+    env.storage().persistent().get(key).unwrap_or(PoolStatus::Active)
+}
+
 pub(crate) fn read_pool_hash(env: &Env) -> BytesN<32> {
     env.storage()
         .instance()
@@ -42,3 +236,32 @@ pub(crate) fn read_pool_hash(env: &Env) -> BytesN<32> {
 pub(crate) fn write_pool_hash(env: &Env, hash: &BytesN<32>) {
     env.storage().instance().set(&DataKey::PoolHash, hash)
 }
+
+pub(crate) fn read_pool_releases(env: &Env) -> Vec<PoolRelease> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PoolReleases)
+        .unwrap_or(Vec::new(env))
+}
+
+// the current pool hash is also updated, new pools are always deployed with the latest release.
+pub(crate) fn add_pool_release(env: &Env, version: u32, hash: &BytesN<32>) {
+    let mut releases = read_pool_releases(env);
+    releases.push_back(PoolRelease {
+        version,
+        hash: hash.clone(),
+        ledger: env.ledger().sequence(),
+    });
+
+    env.storage().persistent().set(&DataKey::PoolReleases, &releases);
+    write_pool_hash(env, hash);
+}
+
+// factories deployed before releases were tracked only know the first version.
+pub(crate) fn read_latest_version(env: &Env) -> u32 {
+    if let Some(release) = read_pool_releases(env).last() {
+        release.version
+    } else {
+        1
+    }
+}
@@ -0,0 +1,69 @@
+use crate::contract::{XycloansFactory, XycloansFactoryClient};
+use crate::pool;
+use crate::types::Error;
+
+use soroban_sdk::{testutils::Address as _, Address, BytesN, Env};
+
+fn plugged_pool(env: &Env, token_id: &Address) -> Address {
+    env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>))
+}
+
+// Tests that a pool for the right token can be plugged in and
+// that replacing it must be explicitly requested.
+#[test]
+fn set_pool() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+
+    let protocol = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(XycloansFactory, (protocol.clone(), BytesN::from_array(&env, &[0; 32])));
+    let factory_client = XycloansFactoryClient::new(&env, &factory_id);
+
+    let pool0 = plugged_pool(&env, &token_id);
+    factory_client.set_pool(&token_id, &8, &pool0, &false);
+    assert_eq!(factory_client.get_pool_address(&token_id), pool0);
+    assert_eq!(factory_client.pool_count(), 1);
+
+    let pool1 = plugged_pool(&env, &token_id);
+    assert_eq!(
+        factory_client.try_set_pool(&token_id, &8, &pool1, &false),
+        Err(Ok(Error::PoolExists))
+    );
+
+    factory_client.set_pool(&token_id, &8, &pool1, &true);
+    assert_eq!(factory_client.get_pool_address(&token_id), pool1);
+    assert_eq!(factory_client.predict_pool_address(&token_id), pool1);
+    assert_eq!(factory_client.token_of(&pool1), token_id);
+    assert!(factory_client.try_token_of(&pool0).is_err());
+
+    // replacing doesn't add a registry entry.
+    assert_eq!(factory_client.pool_count(), 1);
+}
+
+// Tests that a pool for a different token can't be plugged in.
+#[test]
+fn set_pool_token_mismatch() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+
+    let protocol = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+    let other_token_id = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(XycloansFactory, (protocol.clone(), BytesN::from_array(&env, &[0; 32])));
+    let factory_client = XycloansFactoryClient::new(&env, &factory_id);
+
+    let pool_addr = plugged_pool(&env, &other_token_id);
+    assert_eq!(
+        factory_client.try_set_pool(&token_id, &8, &pool_addr, &false),
+        Err(Ok(Error::TokenMismatch))
+    );
+
+    // not a pool at all.
+    assert_eq!(
+        factory_client.try_set_pool(&token_id, &8, &other_token_id, &false),
+        Err(Ok(Error::TokenMismatch))
+    );
+}
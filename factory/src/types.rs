@@ -1,20 +1,112 @@
-use soroban_sdk::{contracterror, contracttype, Address};
+use soroban_sdk::{contracterror, contracttype, Address, BytesN};
 
 #[derive(Clone)]
 #[contracttype]
 pub enum DataKey {
     Admin,
+    PendingAdmin,
+    Role(Role),
     PoolHash,
-    Pool(Address),
+    PoolReleases,
+    Permissionless,
+    AllowlistMode,
+    SacAllowedByDefault,
+    PoolCount,
+    Pool(Address, u32),
+    Tiers(Address),
+    PoolAt(u32),
+    TokenOf(Address),
+    PoolVersion(Address),
+    PoolStatus(Address),
+    Allowed(Address),
+    Blocked(Address),
+    Paused,
+    TokenPaused(Address),
 }
 
-#[derive(Copy, Clone, Debug)]
+/// Privileges that the admin can delegate, each role defaults to the admin.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Role {
+    /// Can deploy pools.
+    Deployer,
+    /// Can change the pool WASM hash.
+    HashManager,
+    /// Can pause the protocol.
+    Guardian,
+}
+
+/// Lifecycle of a pool.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum PoolStatus {
+    Active,
+    /// Doesn't accept deposits anymore.
+    Deprecated,
+    /// Doesn't accept deposits nor lend anymore.
+    Sunset,
+}
+
+/// Largest loan a pool lends.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum MaxLoan {
+    Unlimited,
+    /// Amount of the pool's token.
+    Amount(i128),
+    /// Share of the pool's liquidity, in basis points.
+    Share(u32),
+}
+
+/// Limits on the loans of a pool.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LoanLimits {
+    /// Smallest loan the pool lends, 0 for no limit.
+    pub min_loan: i128,
+    pub max_loan: MaxLoan,
+    /// Largest amount lent out within a ledger, 0 for no limit.
+    pub max_volume_per_ledger: i128,
+}
+
+/// A pool WASM hash release.
+#[derive(Clone)]
+#[contracttype]
+pub struct PoolRelease {
+    pub version: u32,
+    pub hash: BytesN<32>,
+    /// Ledger at which the release was set.
+    pub ledger: u32,
+}
+
+/// Entry of the pool registry.
+#[derive(Clone)]
+#[contracttype]
+pub struct PoolInfo {
+    pub token: Address,
+    pub tier: u32,
+    pub pool: Address,
+    pub version: u32,
+    pub status: PoolStatus,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[contracterror]
 #[repr(u32)]
 pub enum Error {
-    AlreadyInitialized = 0,
     NotInitialized = 1,
     NotAdmin = 2,
     PoolExists = 3,
-    NoPool = 4
+    NoPool = 4,
+    InvalidBatch = 5,
+    LoanNotRepaid = 6,
+    InvalidTier = 7,
+    NoPendingAdmin = 8,
+    InvalidVersion = 9,
+    TokenMismatch = 10,
+    InvalidStatus = 11,
+    TokenNotAllowed = 12,
+    Paused = 13,
+    Overflow = 14,
+    InvalidSalt = 15
 }
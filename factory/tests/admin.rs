@@ -0,0 +1,71 @@
+use soroban_sdk::{testutils::Address as _, Address, Env};
+
+mod factory {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_factory.wasm");
+}
+
+mod pool {
+    use soroban_sdk::contractimport;
+
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+// Tests that the admin is only rotated once the proposed admin accepts.
+#[test]
+fn test_admin_transfer() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let new_admin = Address::generate(&env);
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    // nothing to accept yet.
+    assert!(factory_client.try_accept_admin().is_err());
+
+    factory_client.propose_admin(&new_admin);
+    assert_eq!(factory_client.admin(), protocol);
+
+    factory_client.accept_admin();
+    assert_eq!(env.auths()[0].0, new_admin);
+    assert_eq!(factory_client.admin(), new_admin);
+
+    // the proposal is consumed.
+    assert!(factory_client.try_accept_admin().is_err());
+}
+
+// Tests that roles default to the admin and that the deployer
+// role is the one authorizing pool deployments.
+#[test]
+fn test_roles() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let deployer = Address::generate(&env);
+    let guardian = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    assert_eq!(factory_client.role(&factory::Role::Deployer), protocol);
+    assert_eq!(factory_client.role(&factory::Role::HashManager), protocol);
+    assert_eq!(factory_client.role(&factory::Role::Guardian), protocol);
+
+    factory_client.set_role(&factory::Role::Deployer, &deployer);
+    factory_client.set_role(&factory::Role::Guardian, &guardian);
+    assert_eq!(factory_client.role(&factory::Role::Deployer), deployer);
+    assert_eq!(factory_client.role(&factory::Role::Guardian), guardian);
+    assert_eq!(factory_client.role(&factory::Role::HashManager), protocol);
+
+    factory_client.deploy_canonical_pool(&token_id);
+    assert_eq!(env.auths()[0].0, deployer);
+}
@@ -1,4 +1,4 @@
-use soroban_sdk::{testutils::Address as _, token, Address, BytesN, Env};
+use soroban_sdk::{testutils::Address as _, token, vec, xdr::ToXdr, Address, BytesN, Env, Error};
 
 mod factory {
     use soroban_sdk::contractimport;
@@ -23,11 +23,10 @@ fn test_deployer() {
 
     let token_address = env.register_stellar_asset_contract(token_admin);
 
-    let factory_id = env.register_contract_wasm(&None, factory::WASM);
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
     let factory_client = factory::Client::new(&env, &factory_id);
 
-    factory_client.initialize(&protocol, &pool_wasm_hash);
-    factory_client.deploy_pool(&token_address, &BytesN::from_array(&env, &[0; 32]));
+    factory_client.deploy_canonical_pool(&token_address);
 
     assert!(factory_client.try_get_pool_address(&token_address).is_ok());
 }
@@ -47,11 +46,10 @@ fn test_deposit() {
     let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let factory_id = env.register_contract_wasm(&None, factory::WASM);
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
     let factory_client = factory::Client::new(&env, &factory_id);
 
-    factory_client.initialize(&protocol, &pool_wasm_hash);
-    factory_client.deploy_pool(&token_id, &BytesN::from_array(&env, &[0; 32]));
+    factory_client.deploy_canonical_pool(&token_id);
 
     let user = Address::generate(&env);
     let amount = 1000 * 10_i128.pow(7);
@@ -67,3 +65,215 @@ fn test_deposit() {
         amount
     );
 }
+
+// Tests that deployed pools are enumerable through the registry.
+#[test]
+fn test_registry() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+
+    let token0 = env.register_stellar_asset_contract(Address::generate(&env));
+    let token1 = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    assert_eq!(factory_client.pool_count(), 0);
+
+    let pool0 = factory_client.deploy_canonical_pool(&token0);
+    let pool1 = factory_client.deploy_canonical_pool(&token1);
+    assert_eq!(factory_client.pool_count(), 2);
+
+    let pools = factory_client.list_pools(&0, &10);
+    assert_eq!(pools.len(), 2);
+
+    let first = pools.get(0).unwrap();
+    assert_eq!(first.token, token0);
+    assert_eq!(first.pool, pool0);
+    assert_eq!(first.version, 1);
+    assert_eq!(first.status, factory::PoolStatus::Active);
+
+    let page = factory_client.list_pools(&1, &1);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().pool, pool1);
+    assert_eq!(factory_client.list_pools(&2, &1).len(), 0);
+
+    assert_eq!(factory_client.token_of(&pool1), token1);
+    assert!(factory_client.try_token_of(&token1).is_err());
+}
+
+// Tests that a token can have one pool per fee tier and
+// that each pool charges the fee of its tier.
+#[test]
+fn test_fee_tiers() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    let pool_30 = factory_client.deploy_pool(&token_id, &30, &BytesN::from_array(&env, &[0; 32]));
+    assert_eq!(factory_client.get_pool_address(&token_id), pool_30);
+
+    let pool_3 = factory_client.deploy_pool(&token_id, &3, &BytesN::from_array(&env, &[1; 32]));
+    let pool_8 = factory_client.deploy_canonical_pool(&token_id);
+
+    // the default tier is the canonical pool.
+    assert_eq!(factory_client.get_pool_address(&token_id), pool_8);
+    assert_eq!(factory_client.get_tier_pool_address(&token_id, &3), pool_3);
+    assert_eq!(
+        factory_client.get_pools(&token_id),
+        vec![&env, (30, pool_30.clone()), (3, pool_3.clone()), (8, pool_8.clone())]
+    );
+
+    assert_eq!(pool::Client::new(&env, &pool_3).fee_rate(), 3);
+    assert_eq!(pool::Client::new(&env, &pool_30).fee_rate(), 30);
+
+    assert!(factory_client.try_deploy_canonical_pool(&token_id).is_err());
+    assert!(factory_client.try_deploy_pool(&token_id, &5, &BytesN::from_array(&env, &[4; 32])).is_err());
+}
+
+// Tests that pools record the pool version they were deployed with.
+#[test]
+fn test_pool_versions() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let token0 = env.register_stellar_asset_contract(Address::generate(&env));
+    let token1 = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    assert_eq!(factory_client.latest_version(), 1);
+
+    let pool0 = factory_client.deploy_canonical_pool(&token0);
+
+    // the same WASM is released again as a new version for simplicity.
+    factory_client.set_pool_hash(&pool_wasm_hash, &2);
+    assert_eq!(factory_client.latest_version(), 2);
+
+    let pool1 = factory_client.deploy_canonical_pool(&token1);
+
+    assert_eq!(factory_client.pool_version(&pool0), 1);
+    assert_eq!(factory_client.pool_version(&pool1), 2);
+    assert_eq!(factory_client.list_pools(&1, &1).get(0).unwrap().version, 2);
+
+    let releases = factory_client.pool_releases();
+    assert_eq!(releases.len(), 2);
+    assert_eq!(releases.get(1).unwrap().version, 2);
+    assert_eq!(releases.get(1).unwrap().hash, pool_wasm_hash);
+
+    // versions must increase.
+    assert!(factory_client.try_set_pool_hash(&pool_wasm_hash, &2).is_err());
+}
+
+// Tests that anyone can deploy the canonical pool of a token in permissionless mode
+// and that its address is known beforehand.
+#[test]
+fn test_permissionless_deployer() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let token0 = env.register_stellar_asset_contract(Address::generate(&env));
+    let token1 = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    assert!(!factory_client.permissionless());
+
+    // the deployer authorizes canonical deployments by default.
+    let predicted = factory_client.predict_pool_address(&token0);
+    assert_eq!(factory_client.deploy_canonical_pool(&token0), predicted);
+    assert_eq!(env.auths()[0].0, protocol);
+    assert_eq!(factory_client.get_tier_pool_address(&token0, &8), predicted);
+
+    factory_client.set_permissionless(&true);
+
+    let predicted = factory_client.predict_pool_address(&token1);
+    assert_eq!(factory_client.deploy_canonical_pool(&token1), predicted);
+    assert!(env.auths().is_empty());
+
+    assert!(factory_client.try_deploy_canonical_pool(&token1).is_err());
+}
+
+// Tests that the default tier pool is only deployed at the predicted address,
+// while other tiers use the given salt.
+#[test]
+fn test_predict_custom_salt() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    assert_eq!(
+        factory_client.try_deploy_pool(&token_id, &8, &BytesN::from_array(&env, &[7; 32])),
+        Err(Ok(Error::from_contract_error(factory::Error::InvalidSalt as u32)))
+    );
+
+    let predicted = factory_client.predict_pool_address(&token_id);
+    let salt = env.crypto().sha256(&token_id.clone().to_xdr(&env)).to_bytes();
+    let pool8 = factory_client.deploy_pool(&token_id, &8, &salt);
+    assert_eq!(pool8, predicted);
+    assert_eq!(factory_client.predict_pool_address(&token_id), pool8);
+
+    let pool30 = factory_client.deploy_pool(&token_id, &30, &BytesN::from_array(&env, &[7; 32]));
+    assert_ne!(pool30, predicted);
+    assert_eq!(factory_client.predict_pool_address(&token_id), pool8);
+}
+
+// Tests that a pool can be deployed and seeded with liquidity atomically.
+#[test]
+fn test_deploy_with_liquidity() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let seeder = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+    let token = token::Client::new(&env, &token_id);
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    let amount = 1000 * 10_i128.pow(7);
+    token_admin_client.mint(&seeder, &amount);
+
+    let salt = env.crypto().sha256(&token_id.clone().to_xdr(&env)).to_bytes();
+    let pool_addr = factory_client.deploy_pool_with_liquidity(
+        &token_id,
+        &8,
+        &salt,
+        &seeder,
+        &amount,
+    );
+
+    assert_eq!(pool::Client::new(&env, &pool_addr).shares(&seeder), amount);
+    assert_eq!(token.balance(&pool_addr), amount);
+    assert_eq!(token.balance(&seeder), 0);
+}
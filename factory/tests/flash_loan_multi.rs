@@ -0,0 +1,179 @@
+use soroban_sdk::{
+    contract, contractimpl, symbol_short, testutils::Address as _, token, vec, Address, Bytes, BytesN, Env, Error, Symbol, Vec,
+};
+
+mod factory {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_factory.wasm");
+}
+
+mod pool {
+    use soroban_sdk::contractimport;
+
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+const STROOP: i128 = 10_000_000;
+
+#[contract]
+pub struct MultiReceiver;
+
+#[contractimpl]
+impl MultiReceiver {
+    pub fn init(env: Env, factory: Address, repay: bool) {
+        env.storage().instance().set(&symbol_short!("F"), &factory);
+        env.storage().instance().set(&symbol_short!("R"), &repay);
+    }
+
+    pub fn exec_op_multi(env: Env, _initiator: Address, loans: Vec<(Address, i128, i128)>, _data: Bytes) {
+        let factory: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("F")).unwrap();
+        let repay: bool = env.storage().instance().get::<Symbol, bool>(&symbol_short!("R")).unwrap();
+
+        // perform operations here
+        // ...
+
+        if !repay {
+            return;
+        }
+
+        for (token, amount, fee) in loans.iter() {
+            token::Client::new(&env, &token).approve(
+                &env.current_contract_address(),
+                &factory,
+                &(amount + fee),
+                &(env.ledger().sequence() + 1),
+            );
+        }
+    }
+}
+
+struct Setup<'a> {
+    env: Env,
+    factory: factory::Client<'a>,
+    tokens: [Address; 2],
+    lp: Address,
+    receiver: Address,
+}
+
+fn setup<'a>(repay: bool) -> Setup<'a> {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+    let protocol = Address::generate(&env);
+    let lp = Address::generate(&env);
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    let receiver = env.register_contract(None, MultiReceiver);
+    MultiReceiverClient::new(&env, &receiver).init(&factory_id, &repay);
+
+    let tokens = [
+        env.register_stellar_asset_contract(Address::generate(&env)),
+        env.register_stellar_asset_contract(Address::generate(&env)),
+    ];
+
+    for token_id in tokens.iter() {
+        factory_client.deploy_canonical_pool(token_id);
+
+        let token_admin = token::StellarAssetClient::new(&env, token_id);
+        token_admin.mint(&lp, &(100 * STROOP));
+        token_admin.mint(&receiver, &(10 * STROOP));
+
+        pool::Client::new(&env, &factory_client.get_pool_address(token_id)).deposit(&lp, &(100 * STROOP));
+    }
+
+    Setup { env, factory: factory_client, tokens, lp, receiver }
+}
+
+// Tests that two assets can be borrowed in one batch and
+// that both pools collect the fees.
+#[test]
+fn flash_loan_multi() {
+    let s = setup(true);
+    let initiator = Address::generate(&s.env);
+
+    let loans = vec![
+        &s.env,
+        (s.tokens[0].clone(), 8, 100 * STROOP),
+        (s.tokens[1].clone(), 8, 50 * STROOP),
+    ];
+    s.factory.flash_loan_multi(&initiator, &s.receiver, &loans, &Bytes::new(&s.env));
+
+    let pool0 = pool::Client::new(&s.env, &s.factory.get_pool_address(&s.tokens[0]));
+    let pool1 = pool::Client::new(&s.env, &s.factory.get_pool_address(&s.tokens[1]));
+
+    pool0.update_fee_rewards(&s.lp);
+    pool1.update_fee_rewards(&s.lp);
+
+    assert_eq!(pool0.matured(&s.lp), 800_000);
+    assert_eq!(pool1.matured(&s.lp), 400_000);
+    assert_eq!(token::Client::new(&s.env, &s.tokens[0]).balance(&pool0.address), 100 * STROOP + 800_000);
+    assert_eq!(token::Client::new(&s.env, &s.tokens[1]).balance(&pool1.address), 100 * STROOP + 400_000);
+}
+
+// Tests that the batch is reverted when the receiver doesn't repay.
+#[test]
+fn flash_loan_multi_not_repaid() {
+    let s = setup(false);
+    let initiator = Address::generate(&s.env);
+
+    let loans = vec![
+        &s.env,
+        (s.tokens[0].clone(), 8, 100 * STROOP),
+        (s.tokens[1].clone(), 8, 50 * STROOP),
+    ];
+    assert!(s.factory.try_flash_loan_multi(&initiator, &s.receiver, &loans, &Bytes::new(&s.env)).is_err());
+
+    for token_id in s.tokens.iter() {
+        let token = token::Client::new(&s.env, token_id);
+        assert_eq!(token.balance(&s.factory.get_pool_address(token_id)), 100 * STROOP);
+        assert_eq!(token.balance(&s.receiver), 10 * STROOP);
+    }
+}
+
+// Tests that the same token can't be borrowed twice within a batch.
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #5)")]
+fn flash_loan_multi_duplicate_token() {
+    let s = setup(true);
+    let initiator = Address::generate(&s.env);
+
+    let loans = vec![
+        &s.env,
+        (s.tokens[0].clone(), 8, 10 * STROOP),
+        (s.tokens[0].clone(), 8, 10 * STROOP),
+    ];
+    s.factory.flash_loan_multi(&initiator, &s.receiver, &loans, &Bytes::new(&s.env));
+}
+
+// Tests that every loan of a batch is taken from the pool of its tier.
+#[test]
+fn flash_loan_multi_tiers() {
+    let s = setup(true);
+    let initiator = Address::generate(&s.env);
+
+    let pool30 = s.factory.deploy_pool(&s.tokens[1], &30, &BytesN::from_array(&s.env, &[2; 32]));
+    token::StellarAssetClient::new(&s.env, &s.tokens[1]).mint(&s.lp, &(100 * STROOP));
+    pool::Client::new(&s.env, &pool30).deposit(&s.lp, &(100 * STROOP));
+
+    let loans = vec![
+        &s.env,
+        (s.tokens[0].clone(), 8, 100 * STROOP),
+        (s.tokens[1].clone(), 30, 50 * STROOP),
+    ];
+    s.factory.flash_loan_multi(&initiator, &s.receiver, &loans, &Bytes::new(&s.env));
+
+    let token1 = token::Client::new(&s.env, &s.tokens[1]);
+    assert_eq!(token1.balance(&pool30), 100 * STROOP + 1_500_000);
+    assert_eq!(token1.balance(&s.factory.get_tier_pool_address(&s.tokens[1], &8)), 100 * STROOP);
+
+    // tiers without a pool fail the whole batch.
+    let loans = vec![&s.env, (s.tokens[0].clone(), 3, 10 * STROOP)];
+    assert_eq!(
+        s.factory.try_flash_loan_multi(&initiator, &s.receiver, &loans, &Bytes::new(&s.env)),
+        Err(Ok(Error::from_contract_error(factory::Error::NoPool as u32)))
+    );
+}
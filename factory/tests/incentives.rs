@@ -0,0 +1,133 @@
+use soroban_sdk::{testutils::{Address as _, Ledger}, token, vec, Address, Env, Error};
+
+mod factory {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_factory.wasm");
+}
+
+mod pool {
+    use soroban_sdk::contractimport;
+
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+const STROOP: i128 = 10_000_000;
+
+// Tests that an approved distributor's reward token is streamed to
+// the liquidity providers proportionally to their shares.
+#[test]
+fn incentives() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let distributor = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+    let reward_id = env.register_stellar_asset_contract(Address::generate(&env));
+    let reward = token::Client::new(&env, &reward_id);
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+    let pool_addr = factory_client.deploy_canonical_pool(&token_id);
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    token::StellarAssetClient::new(&env, &token_id).mint(&user1, &(100 * STROOP));
+    token::StellarAssetClient::new(&env, &token_id).mint(&user2, &(300 * STROOP));
+    token::StellarAssetClient::new(&env, &reward_id).mint(&distributor, &1_000_000);
+
+    pool_client.deposit(&user1, &(100 * STROOP));
+    pool_client.deposit(&user2, &(300 * STROOP));
+
+    let end_ledger = env.ledger().sequence() + 100;
+    assert_eq!(
+        pool_client.try_fund_incentive(&distributor, &reward_id, &1000, &end_ledger),
+        Err(Ok(Error::from_contract_error(pool::Error::NotDistributor as u32)))
+    );
+
+    factory_client.set_distributor(&token_id, &8, &distributor, &true);
+    assert_eq!(env.auths()[0].0, protocol);
+    assert!(pool_client.is_distributor(&distributor));
+
+    assert_eq!(
+        pool_client.try_fund_incentive(&distributor, &token_id, &1000, &end_ledger),
+        Err(Ok(Error::from_contract_error(pool::Error::InvalidRewardToken as u32)))
+    );
+
+    assert_eq!(pool_client.fund_incentive(&distributor, &reward_id, &1000, &end_ledger), 100_000);
+    assert_eq!(reward.balance(&pool_addr), 100_000);
+    assert_eq!(pool_client.reward_tokens(), vec![&env, reward_id.clone()]);
+
+    // the program can't be topped up while it runs.
+    assert_eq!(
+        pool_client.try_fund_incentive(&distributor, &reward_id, &1000, &(end_ledger + 100)),
+        Err(Ok(Error::from_contract_error(pool::Error::IncentiveActive as u32)))
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number += 50);
+    assert_eq!(pool_client.claim_incentives(&user1), vec![&env, (reward_id.clone(), 12_500)]);
+
+    // nothing is distributed after the end ledger.
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    assert_eq!(pool_client.claim_incentives(&user1), vec![&env, (reward_id.clone(), 12_500)]);
+    assert_eq!(pool_client.claim_incentives(&user2), vec![&env, (reward_id.clone(), 75_000)]);
+    assert_eq!(pool_client.claim_incentives(&user2), vec![&env]);
+
+    assert_eq!(reward.balance(&user1), 25_000);
+    assert_eq!(reward.balance(&user2), 75_000);
+    assert_eq!(reward.balance(&pool_addr), 0);
+
+    // the pool's own fee accounting is untouched.
+    assert_eq!(pool_client.matured(&user1), 0);
+    assert_eq!(token::Client::new(&env, &token_id).balance(&pool_addr), 400 * STROOP);
+}
+
+// Tests that an incentive program whose accounting overflows is stopped
+// instead of blocking the withdrawals of the liquidity providers.
+#[test]
+fn incentive_overflow() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let distributor = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+    let reward_id = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+    let pool_addr = factory_client.deploy_canonical_pool(&token_id);
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    token::StellarAssetClient::new(&env, &token_id).mint(&user, &2);
+    token::StellarAssetClient::new(&env, &reward_id).mint(&distributor, &i128::MAX);
+
+    // a single share is left in the pool.
+    pool_client.deposit(&user, &2);
+    pool_client.withdraw(&user, &1);
+
+    factory_client.set_distributor(&token_id, &8, &distributor, &true);
+    let end_ledger = env.ledger().sequence() + 1;
+    pool_client.fund_incentive(&distributor, &reward_id, &i128::MAX, &end_ledger);
+
+    // the reward per share doesn't fit, the program is stopped.
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+    pool_client.withdraw(&user, &1);
+    assert_eq!(pool_client.shares(&user), 0);
+    assert_eq!(pool_client.incentive(&reward_id).unwrap().reward_per_share, 0);
+    assert_eq!(pool_client.incentive(&reward_id).unwrap().last_ledger, end_ledger);
+
+    pool_client.deposit(&user, &1);
+    assert_eq!(pool_client.exit(&user), 1);
+    assert_eq!(token::Client::new(&env, &token_id).balance(&user), 2);
+}
@@ -0,0 +1,118 @@
+use soroban_sdk::{
+    contract, contractimpl, symbol_short, testutils::Address as _, token, Address, Env, Error, Symbol,
+};
+
+mod factory {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_factory.wasm");
+}
+
+mod pool {
+    use soroban_sdk::contractimport;
+
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+const STROOP: i128 = 10_000_000;
+
+// Tests that deprecated pools stop accepting deposits, sunset pools
+// also stop lending, and that withdrawals stay open throughout.
+#[test]
+fn pool_lifecycle() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+    let token = token::Client::new(&env, &token_id);
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    let pool_addr = factory_client.deploy_canonical_pool(&token_id);
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    FlashLoanReceiverClient::new(&env, &receiver).init(&token_id, &pool_addr);
+
+    token_admin_client.mint(&user, &(200 * STROOP));
+    token_admin_client.mint(&receiver, &(10 * STROOP));
+
+    pool_client.deposit(&user, &(100 * STROOP));
+
+    factory_client.set_pool_status(&token_id, &8, &factory::PoolStatus::Deprecated);
+    assert_eq!(pool_client.status(), pool::PoolStatus::Deprecated);
+    assert_eq!(factory_client.pool_status(&pool_addr), factory::PoolStatus::Deprecated);
+
+    // the deprecated pool isn't resolved anymore unless asked.
+    assert!(factory_client.try_get_pool_address(&token_id).is_err());
+    assert_eq!(factory_client.find_pool(&token_id, &true), pool_addr);
+
+    assert_eq!(
+        pool_client.try_deposit(&user, &(100 * STROOP)),
+        Err(Ok(Error::from_contract_error(pool::Error::PoolDeprecated as u32)))
+    );
+    pool_client.borrow(&receiver, &(10 * STROOP));
+    factory_client.withdraw(&token_id, &8, &user, &(10 * STROOP));
+
+    factory_client.set_pool_status(&token_id, &8, &factory::PoolStatus::Sunset);
+    assert_eq!(pool_client.status(), pool::PoolStatus::Sunset);
+
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &(10 * STROOP)),
+        Err(Ok(Error::from_contract_error(pool::Error::PoolSunset as u32)))
+    );
+
+    factory_client.update_fee_rewards(&token_id, &8, &user);
+    factory_client.withdraw_matured(&token_id, &8, &user);
+    factory_client.withdraw(&token_id, &8, &user, &(90 * STROOP));
+    assert_eq!(token.balance(&user), 200 * STROOP + 80_000);
+
+    // sunset is final.
+    assert!(factory_client
+        .try_set_pool_status(&token_id, &8, &factory::PoolStatus::Active)
+        .is_err());
+}
+
+#[contract]
+pub struct FlashLoanReceiver;
+
+fn compute_fee(amount: &i128) -> i128 {
+    amount / 1250
+}
+
+#[contractimpl]
+impl FlashLoanReceiver {
+    pub fn init(e: Env, token: Address, fl_addr: Address) {
+        e.storage().instance().set(&symbol_short!("T"), &token);
+        e.storage().instance().set(&symbol_short!("FL"), &fl_addr);
+    }
+
+    pub fn exec_op(e: Env) {
+        let token_client = token::Client::new(
+            &e,
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("T"))
+                .unwrap(),
+        );
+
+        let total_amount = (10 * STROOP) + compute_fee(&(10 * STROOP));
+
+        token_client.approve(
+            &e.current_contract_address(),
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("FL"))
+                .unwrap(),
+            &total_amount,
+            &(e.ledger().sequence() + 1),
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use soroban_sdk::{
+    contract, contractimpl, symbol_short, testutils::{Address as _, Ledger}, token, Address, Env, Error, Symbol,
+};
+
+mod factory {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_factory.wasm");
+}
+
+mod pool {
+    use soroban_sdk::contractimport;
+
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+const STROOP: i128 = 10_000_000;
+
+// Tests that the admin can limit the size of a pool's loans, either in
+// absolute terms or as a share of the liquidity, as well as the volume
+// lent out within a ledger.
+#[test]
+fn loan_limits() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let lp = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+    let pool_addr = factory_client.deploy_canonical_pool(&token_id);
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    let receiver = env.register(FlashLoanReceiver, ());
+    FlashLoanReceiverClient::new(&env, &receiver).init(&token_id, &pool_addr);
+
+    token::StellarAssetClient::new(&env, &token_id).mint(&lp, &(100 * STROOP));
+    token::StellarAssetClient::new(&env, &token_id).mint(&receiver, &(10 * STROOP));
+
+    pool_client.deposit(&lp, &(100 * STROOP));
+
+    // absolute limits.
+    factory_client.set_loan_limits(&token_id, &8, &factory::LoanLimits {
+        min_loan: STROOP,
+        max_loan: factory::MaxLoan::Amount(50 * STROOP),
+        max_volume_per_ledger: 0,
+    });
+    assert_eq!(env.auths()[0].0, protocol);
+    assert_eq!(pool_client.loan_limits().max_loan, pool::MaxLoan::Amount(50 * STROOP));
+
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &(STROOP / 2)),
+        Err(Ok(Error::from_contract_error(pool::Error::LoanTooSmall as u32)))
+    );
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &(60 * STROOP)),
+        Err(Ok(Error::from_contract_error(pool::Error::LoanTooLarge as u32)))
+    );
+    pool_client.borrow(&receiver, &(50 * STROOP));
+
+    // a quarter of the liquidity.
+    factory_client.set_loan_limits(&token_id, &8, &factory::LoanLimits {
+        min_loan: 0,
+        max_loan: factory::MaxLoan::Share(2_500),
+        max_volume_per_ledger: 0,
+    });
+
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &(26 * STROOP)),
+        Err(Ok(Error::from_contract_error(pool::Error::LoanTooLarge as u32)))
+    );
+    pool_client.borrow(&receiver, &(25 * STROOP));
+
+    // the volume isn't tracked while it's not limited.
+    assert_eq!(pool_client.ledger_volume(), 0);
+
+    // volume within a ledger.
+    factory_client.set_loan_limits(&token_id, &8, &factory::LoanLimits {
+        min_loan: 0,
+        max_loan: factory::MaxLoan::Unlimited,
+        max_volume_per_ledger: 40 * STROOP,
+    });
+
+    pool_client.borrow(&receiver, &(25 * STROOP));
+    assert_eq!(pool_client.ledger_volume(), 25 * STROOP);
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &(20 * STROOP)),
+        Err(Ok(Error::from_contract_error(pool::Error::VolumeExceeded as u32)))
+    );
+
+    // the volume resets on the next ledger.
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+    assert_eq!(pool_client.ledger_volume(), 0);
+    pool_client.borrow(&receiver, &(20 * STROOP));
+    assert_eq!(pool_client.ledger_volume(), 20 * STROOP);
+
+    // inconsistent limits are rejected.
+    assert!(factory_client
+        .try_set_loan_limits(&token_id, &8, &factory::LoanLimits {
+            min_loan: 0,
+            max_loan: factory::MaxLoan::Share(0),
+            max_volume_per_ledger: 0,
+        })
+        .is_err());
+    assert!(factory_client
+        .try_set_loan_limits(&token_id, &8, &factory::LoanLimits {
+            min_loan: 10 * STROOP,
+            max_loan: factory::MaxLoan::Amount(STROOP),
+            max_volume_per_ledger: 0,
+        })
+        .is_err());
+}
+
+#[contract]
+pub struct FlashLoanReceiver;
+
+#[contractimpl]
+impl FlashLoanReceiver {
+    pub fn init(env: Env, token: Address, fl_addr: Address) {
+        env.storage().instance().set(&symbol_short!("T"), &token);
+        env.storage().instance().set(&symbol_short!("FL"), &fl_addr);
+    }
+
+    pub fn exec_op(env: Env) {
+        let token_client = token::Client::new(
+            &env,
+            &env.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("T"))
+                .unwrap(),
+        );
+
+        // enough for any loan of the pool with fees.
+        token_client.approve(
+            &env.current_contract_address(),
+            &env.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("FL"))
+                .unwrap(),
+            &(1000 * STROOP),
+            &(env.ledger().sequence() + 1),
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use inner_receiver::{InnerReceiver, InnerReceiverClient};
+use nested_receiver::{NestedReceiver, NestedReceiverClient};
+use soroban_sdk::{testutils::Address as _, token, vec, Address, Bytes, Env};
+
+mod factory {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_factory.wasm");
+}
+
+mod pool {
+    use soroban_sdk::contractimport;
+
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+const STROOP: i128 = 10_000_000;
+
+// Receiver of a batch loan which borrows again from `pool` while the batch is out,
+// the outcome of the nested loan is recorded: 0 if it succeeded, the pool's error otherwise.
+mod nested_receiver {
+    use super::pool;
+    use soroban_sdk::{contract, contractimpl, symbol_short, token, Address, Bytes, Env, Symbol, Vec};
+
+    #[contract]
+    pub struct NestedReceiver;
+
+    #[contractimpl]
+    impl NestedReceiver {
+        pub fn init(env: Env, factory: Address, pool: Address, inner: Address, amount: i128) {
+            env.storage().instance().set(&symbol_short!("F"), &factory);
+            env.storage().instance().set(&symbol_short!("P"), &pool);
+            env.storage().instance().set(&symbol_short!("I"), &inner);
+            env.storage().instance().set(&symbol_short!("A"), &amount);
+        }
+
+        pub fn exec_op_multi(env: Env, _initiator: Address, loans: Vec<(Address, i128, i128)>, _data: Bytes) {
+            let factory: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("F")).unwrap();
+            let pool: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("P")).unwrap();
+            let inner: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("I")).unwrap();
+            let amount: i128 = env.storage().instance().get::<Symbol, i128>(&symbol_short!("A")).unwrap();
+
+            let code = match pool::Client::new(&env, &pool).try_borrow(&inner, &amount) {
+                Ok(Ok(())) => 0,
+                Err(Ok(error)) => error.get_code(),
+                _ => u32::MAX,
+            };
+            env.storage().instance().set(&symbol_short!("C"), &code);
+
+            for (token, amount, fee) in loans.iter() {
+                token::Client::new(&env, &token).approve(
+                    &env.current_contract_address(),
+                    &factory,
+                    &(amount + fee),
+                    &(env.ledger().sequence() + 1),
+                );
+            }
+        }
+
+        pub fn code(env: Env) -> u32 {
+            env.storage().instance().get::<Symbol, u32>(&symbol_short!("C")).unwrap()
+        }
+    }
+}
+
+// Receiver of the nested loan, it repays it with fees.
+mod inner_receiver {
+    use soroban_sdk::{contract, contractimpl, symbol_short, token, Address, Env, Symbol};
+
+    #[contract]
+    pub struct InnerReceiver;
+
+    #[contractimpl]
+    impl InnerReceiver {
+        pub fn init(env: Env, token: Address, pool: Address, amount: i128) {
+            env.storage().instance().set(&symbol_short!("T"), &token);
+            env.storage().instance().set(&symbol_short!("P"), &pool);
+            env.storage().instance().set(&symbol_short!("A"), &amount);
+        }
+
+        pub fn exec_op(env: Env) {
+            let token: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("T")).unwrap();
+            let pool: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("P")).unwrap();
+            let amount: i128 = env.storage().instance().get::<Symbol, i128>(&symbol_short!("A")).unwrap();
+
+            token::Client::new(&env, &token).approve(
+                &env.current_contract_address(),
+                &pool,
+                &(amount + amount / 1250),
+                &(env.ledger().sequence() + 1),
+            );
+        }
+    }
+}
+
+struct Setup<'a> {
+    env: Env,
+    factory: factory::Client<'a>,
+    tokens: [Address; 2],
+    pools: [Address; 2],
+}
+
+fn setup<'a>() -> Setup<'a> {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+    let protocol = Address::generate(&env);
+    let lp = Address::generate(&env);
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    let tokens = [
+        env.register_stellar_asset_contract(Address::generate(&env)),
+        env.register_stellar_asset_contract(Address::generate(&env)),
+    ];
+
+    for token_id in tokens.iter() {
+        factory_client.deploy_canonical_pool(token_id);
+
+        token::StellarAssetClient::new(&env, token_id).mint(&lp, &(100 * STROOP));
+        pool::Client::new(&env, &factory_client.get_pool_address(token_id)).deposit(&lp, &(100 * STROOP));
+    }
+
+    let pools = [
+        factory_client.get_pool_address(&tokens[0]),
+        factory_client.get_pool_address(&tokens[1]),
+    ];
+
+    Setup { env, factory: factory_client, tokens, pools }
+}
+
+// Sets up the receivers for a batch loan of 50 units of the first token
+// during which `nested` units of `token` are borrowed from `pool`.
+fn nested_receiver(s: &Setup, token: &Address, pool: &Address, nested: i128) -> Address {
+    let inner = s.env.register(InnerReceiver, ());
+    InnerReceiverClient::new(&s.env, &inner).init(token, pool, &nested);
+
+    let receiver = s.env.register(NestedReceiver, ());
+    NestedReceiverClient::new(&s.env, &receiver).init(&s.factory.address, pool, &inner, &nested);
+
+    // enough to pay the fees.
+    token::StellarAssetClient::new(&s.env, &s.tokens[0]).mint(&receiver, &STROOP);
+    token::StellarAssetClient::new(&s.env, token).mint(&inner, &STROOP);
+
+    receiver
+}
+
+// Tests that a pool doesn't lend again while one of its loans is out.
+#[test]
+fn nested_same_pool() {
+    let s = setup();
+    let initiator = Address::generate(&s.env);
+    let receiver = nested_receiver(&s, &s.tokens[0], &s.pools[0], 10 * STROOP);
+
+    let loans = vec![&s.env, (s.tokens[0].clone(), 8, 50 * STROOP)];
+    s.factory.flash_loan_multi(&initiator, &receiver, &loans, &Bytes::new(&s.env));
+
+    // the nested loan failed, only the batch paid a fee.
+    assert_eq!(NestedReceiverClient::new(&s.env, &receiver).code(), pool::Error::NestedLoan as u32);
+    assert_eq!(pool::Client::new(&s.env, &s.pools[0]).liabilities(), 400_000);
+    assert_eq!(token::Client::new(&s.env, &s.tokens[0]).balance(&s.pools[0]), 100 * STROOP + 400_000);
+}
+
+// Tests that a loan from another pool can be nested within a loan.
+#[test]
+fn nested_other_pool() {
+    let s = setup();
+    let initiator = Address::generate(&s.env);
+    let receiver = nested_receiver(&s, &s.tokens[1], &s.pools[1], 10 * STROOP);
+
+    let loans = vec![&s.env, (s.tokens[0].clone(), 8, 50 * STROOP)];
+    s.factory.flash_loan_multi(&initiator, &receiver, &loans, &Bytes::new(&s.env));
+
+    // both loans paid their fee.
+    assert_eq!(NestedReceiverClient::new(&s.env, &receiver).code(), 0);
+    assert_eq!(pool::Client::new(&s.env, &s.pools[0]).liabilities(), 400_000);
+    assert_eq!(pool::Client::new(&s.env, &s.pools[1]).liabilities(), 80_000);
+    assert_eq!(token::Client::new(&s.env, &s.tokens[1]).balance(&s.pools[1]), 100 * STROOP + 80_000);
+}
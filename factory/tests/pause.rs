@@ -0,0 +1,245 @@
+use soroban_sdk::{
+    contract, contractimpl, symbol_short, testutils::Address as _, token, Address, BytesN, Env, Error, Symbol,
+};
+
+mod factory {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_factory.wasm");
+}
+
+mod pool {
+    use soroban_sdk::contractimport;
+
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+const STROOP: i128 = 10_000_000;
+
+// Tests that a global pause stops borrows and deposits on every pool,
+// including the ones deployed while paused, and that withdrawals stay open.
+#[test]
+fn global_pause() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let token0 = env.register_stellar_asset_contract(Address::generate(&env));
+    let token1 = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+    factory_client.set_role(&factory::Role::Guardian, &guardian);
+
+    let pool_addr = factory_client.deploy_canonical_pool(&token0);
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    FlashLoanReceiverClient::new(&env, &receiver).init(&token0, &pool_addr);
+
+    token::StellarAssetClient::new(&env, &token0).mint(&user, &(200 * STROOP));
+    token::StellarAssetClient::new(&env, &token0).mint(&receiver, &(10 * STROOP));
+    pool_client.deposit(&user, &(100 * STROOP));
+
+    assert_eq!(factory_client.pause(&None), 1);
+    assert_eq!(env.auths()[0].0, guardian);
+    assert!(factory_client.paused());
+
+    // loans and deposits routed through the factory stop right away.
+    assert_eq!(
+        factory_client.try_deposit(&token0, &8, &user, &(100 * STROOP)),
+        Err(Ok(Error::from_contract_error(factory::Error::Paused as u32)))
+    );
+    assert_eq!(
+        factory_client.try_borrow(&token0, &8, &receiver, &(10 * STROOP)),
+        Err(Ok(Error::from_contract_error(factory::Error::Paused as u32)))
+    );
+
+    // the first pools of the registry are synced in the same call.
+    assert!(pool_client.paused());
+
+    assert_eq!(
+        pool_client.try_deposit(&user, &(100 * STROOP)),
+        Err(Ok(Error::from_contract_error(pool::Error::Paused as u32)))
+    );
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &(10 * STROOP)),
+        Err(Ok(Error::from_contract_error(pool::Error::Paused as u32)))
+    );
+
+    // pools deployed during the pause are paused too.
+    let new_pool = factory_client.deploy_canonical_pool(&token1);
+    assert!(pool::Client::new(&env, &new_pool).paused());
+
+    factory_client.withdraw(&token0, &8, &user, &(50 * STROOP));
+    assert_eq!(token::Client::new(&env, &token0).balance(&user), 150 * STROOP);
+
+    assert_eq!(factory_client.unpause(&None), 2);
+    assert_eq!(env.auths()[0].0, guardian);
+    assert!(!pool_client.paused());
+    assert!(!pool::Client::new(&env, &new_pool).paused());
+
+    pool_client.borrow(&receiver, &(10 * STROOP));
+}
+
+// Tests that the guardian reaches the pools beyond the first page of the registry
+// through `sync_paused()`, after which no pool can be borrowed from or deposited in directly.
+#[test]
+fn global_pause_sync() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let user = Address::generate(&env);
+    let receiver = Address::generate(&env);
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+    factory_client.set_role(&factory::Role::Guardian, &guardian);
+
+    for i in 0..4_u8 {
+        let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+        factory_client.deploy_canonical_pool(&token_id);
+        factory_client.deploy_pool(&token_id, &3, &BytesN::from_array(&env, &[2 * i; 32]));
+        factory_client.deploy_pool(&token_id, &30, &BytesN::from_array(&env, &[2 * i + 1; 32]));
+    }
+    assert_eq!(factory_client.pool_count(), 12);
+
+    let next = factory_client.pause(&None);
+    assert_eq!(env.auths()[0].0, guardian);
+    assert_eq!(next, 10);
+
+    let pools = factory_client.list_pools(&0, &12);
+    for (index, info) in pools.iter().enumerate() {
+        assert_eq!(pool::Client::new(&env, &info.pool).paused(), index < 10);
+    }
+
+    assert_eq!(factory_client.sync_paused(&next, &10), 12);
+
+    for info in pools.iter() {
+        let pool_client = pool::Client::new(&env, &info.pool);
+        assert_eq!(
+            pool_client.try_deposit(&user, &(10 * STROOP)),
+            Err(Ok(Error::from_contract_error(pool::Error::Paused as u32)))
+        );
+        assert_eq!(
+            pool_client.try_borrow(&receiver, &(10 * STROOP)),
+            Err(Ok(Error::from_contract_error(pool::Error::Paused as u32)))
+        );
+    }
+}
+
+// Tests that pausing a token only affects its pools and that a token
+// stays paused until both its own and the global pause are lifted.
+#[test]
+fn token_pause() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let token0 = env.register_stellar_asset_contract(Address::generate(&env));
+    let token1 = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    let pool00 = pool::Client::new(&env, &factory_client.deploy_canonical_pool(&token0));
+    let pool03 = pool::Client::new(&env, &factory_client.deploy_pool(&token0, &3, &BytesN::from_array(&env, &[1; 32])));
+    let pool1 = pool::Client::new(&env, &factory_client.deploy_canonical_pool(&token1));
+
+    token::StellarAssetClient::new(&env, &token1).mint(&user, &(100 * STROOP));
+
+    factory_client.pause(&Some(token0.clone()));
+    assert!(factory_client.is_paused(&token0));
+    assert!(!factory_client.is_paused(&token1));
+    assert!(pool00.paused() && pool03.paused());
+    assert!(!pool1.paused());
+
+    pool1.deposit(&user, &(50 * STROOP));
+
+    factory_client.pause(&None);
+    factory_client.unpause(&None);
+    assert!(pool00.paused() && pool03.paused());
+    assert!(!pool1.paused());
+
+    factory_client.unpause(&Some(token0.clone()));
+    assert!(!factory_client.is_paused(&token0));
+    assert!(!pool00.paused() && !pool03.paused());
+}
+
+// Tests that pools can only be paused through their factory.
+#[test]
+fn pause_only_factory() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+    let protocol = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+    let pool_client = pool::Client::new(&env, &factory_client.deploy_canonical_pool(&token_id));
+
+    pool_client.set_paused(&true);
+    assert_eq!(env.auths()[0].0, factory_id);
+
+    // standalone pools have no factory to pause them.
+    let standalone = pool::Client::new(&env, &env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>)));
+    assert_eq!(
+        standalone.try_set_paused(&true),
+        Err(Ok(Error::from_contract_error(pool::Error::NotManaged as u32)))
+    );
+}
+
+#[contract]
+pub struct FlashLoanReceiver;
+
+fn compute_fee(amount: &i128) -> i128 {
+    amount / 1250
+}
+
+#[contractimpl]
+impl FlashLoanReceiver {
+    pub fn init(e: Env, token: Address, fl_addr: Address) {
+        e.storage().instance().set(&symbol_short!("T"), &token);
+        e.storage().instance().set(&symbol_short!("FL"), &fl_addr);
+    }
+
+    pub fn exec_op(e: Env) {
+        let token_client = token::Client::new(
+            &e,
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("T"))
+                .unwrap(),
+        );
+
+        let total_amount = (10 * STROOP) + compute_fee(&(10 * STROOP));
+
+        token_client.approve(
+            &e.current_contract_address(),
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("FL"))
+                .unwrap(),
+            &total_amount,
+            &(e.ledger().sequence() + 1),
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use soroban_sdk::{
+    contract, contractimpl, symbol_short,
+    testutils::{Address as _, AuthorizedFunction},
+    token, vec, Address, BytesN, Env, Error, IntoVal, Symbol,
+};
+
+mod factory {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_factory.wasm");
+}
+
+mod pool {
+    use soroban_sdk::contractimport;
+
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+const STROOP: i128 = 10_000_000;
+
+// Tests that liquidity providers and borrowers can go through
+// the whole pool lifecycle by only knowing the factory and the token.
+#[test]
+fn router_lifecycle() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let token_admin = Address::generate(&env);
+    let protocol = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(token_admin);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+    let token = token::Client::new(&env, &token_id);
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    factory_client.deploy_canonical_pool(&token_id);
+    let pool_addr = factory_client.get_pool_address(&token_id);
+
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    FlashLoanReceiverClient::new(&env, &receiver).init(&token_id, &pool_addr);
+
+    token_admin_client.mint(&user, &(100 * STROOP));
+    token_admin_client.mint(&receiver, &(10 * STROOP));
+
+    factory_client.deposit(&token_id, &8, &user, &(100 * STROOP));
+
+    // the user's signature on the factory invocation also covers the pool's deposit.
+    let auths = env.auths();
+    assert_eq!(auths[0].0, user);
+    assert_eq!(
+        auths[0].1.function,
+        AuthorizedFunction::Contract((
+            factory_id.clone(),
+            Symbol::new(&env, "deposit"),
+            (token_id.clone(), 8_u32, user.clone(), 100 * STROOP).into_val(&env)
+        ))
+    );
+    assert_eq!(
+        auths[0].1.sub_invocations[0].function,
+        AuthorizedFunction::Contract((
+            pool_addr.clone(),
+            Symbol::new(&env, "deposit"),
+            (user.clone(), 100 * STROOP).into_val(&env)
+        ))
+    );
+
+    factory_client.borrow(&token_id, &8, &receiver, &(100 * STROOP));
+
+    factory_client.update_fee_rewards(&token_id, &8, &user);
+    factory_client.withdraw_matured(&token_id, &8, &user);
+    assert_eq!(token.balance(&user), 800_000);
+
+    factory_client.withdraw(&token_id, &8, &user, &(100 * STROOP));
+    assert_eq!(token.balance(&user), 100 * STROOP + 800_000);
+    assert_eq!(token.balance(&pool_addr), 0);
+}
+
+// Tests that routing to a token without a pool fails.
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #4)")]
+fn router_no_pool() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+    let protocol = Address::generate(&env);
+    let user = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    factory_client.deposit(&token_id, &8, &user, &(100 * STROOP));
+}
+
+// Tests that matured fees are collected from every pool in one call
+// and that pools without matured fees are skipped.
+#[test]
+fn claim_all() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+    let protocol = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    let token0 = env.register_stellar_asset_contract(Address::generate(&env));
+    let token1 = env.register_stellar_asset_contract(Address::generate(&env));
+
+    for token_id in [&token0, &token1] {
+        factory_client.deploy_canonical_pool(token_id);
+        token::StellarAssetClient::new(&env, token_id).mint(&user, &(100 * STROOP));
+        factory_client.deposit(token_id, &8, &user, &(100 * STROOP));
+    }
+
+    // only the pool of token0 generates yield.
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    FlashLoanReceiverClient::new(&env, &receiver).init(&token0, &factory_client.get_pool_address(&token0));
+    token::StellarAssetClient::new(&env, &token0).mint(&receiver, &(10 * STROOP));
+    factory_client.borrow(&token0, &8, &receiver, &(100 * STROOP));
+
+    let payouts = factory_client.claim_all(&user, &vec![&env, token0.clone(), token1.clone()]);
+    assert_eq!(payouts, vec![&env, (token0.clone(), 800_000), (token1.clone(), 0)]);
+
+    assert_eq!(token::Client::new(&env, &token0).balance(&user), 800_000);
+    assert_eq!(token::Client::new(&env, &token1).balance(&user), 0);
+}
+
+// Tests that the router reaches pools outside of the default tier and
+// that matured fees are collected from every tier of a token.
+#[test]
+fn router_tiers() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+    let protocol = Address::generate(&env);
+    let user = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+    let token = token::Client::new(&env, &token_id);
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    let pool8 = factory_client.deploy_canonical_pool(&token_id);
+    let pool30 = factory_client.deploy_pool(&token_id, &30, &BytesN::from_array(&env, &[1; 32]));
+
+    token::StellarAssetClient::new(&env, &token_id).mint(&user, &(200 * STROOP));
+    factory_client.deposit(&token_id, &8, &user, &(100 * STROOP));
+    factory_client.deposit(&token_id, &30, &user, &(100 * STROOP));
+    assert_eq!(token.balance(&pool8), 100 * STROOP);
+    assert_eq!(token.balance(&pool30), 100 * STROOP);
+
+    // both tiers lend, each with its own fee.
+    let receiver8 = env.register_contract(None, FlashLoanReceiver);
+    FlashLoanReceiverClient::new(&env, &receiver8).init(&token_id, &pool8);
+    let receiver30 = env.register_contract(None, FlashLoanReceiver);
+    FlashLoanReceiverClient::new(&env, &receiver30).init(&token_id, &pool30);
+    token::StellarAssetClient::new(&env, &token_id).mint(&receiver8, &(10 * STROOP));
+    token::StellarAssetClient::new(&env, &token_id).mint(&receiver30, &(10 * STROOP));
+
+    factory_client.borrow(&token_id, &8, &receiver8, &(100 * STROOP));
+    factory_client.borrow(&token_id, &30, &receiver30, &(10 * STROOP));
+    assert_eq!(token.balance(&pool8), 100 * STROOP + 800_000);
+    assert_eq!(token.balance(&pool30), 100 * STROOP + 300_000);
+
+    // a tier without a pool isn't resolved to another one.
+    assert_eq!(
+        factory_client.try_deposit(&token_id, &3, &user, &(10 * STROOP)),
+        Err(Ok(Error::from_contract_error(factory::Error::NoPool as u32)))
+    );
+
+    let payouts = factory_client.claim_all(&user, &vec![&env, token_id.clone()]);
+    assert_eq!(payouts, vec![&env, (token_id.clone(), 1_100_000)]);
+    assert_eq!(token.balance(&user), 1_100_000);
+
+    factory_client.withdraw(&token_id, &30, &user, &(100 * STROOP));
+    assert_eq!(token.balance(&pool30), 0);
+    assert_eq!(token.balance(&pool8), 100 * STROOP);
+}
+
+#[contract]
+pub struct FlashLoanReceiver;
+
+fn compute_fee(amount: &i128) -> i128 {
+    amount / 1250
+}
+
+#[contractimpl]
+impl FlashLoanReceiver {
+    pub fn init(e: Env, token: Address, fl_addr: Address) {
+        e.storage().instance().set(&symbol_short!("T"), &token);
+        e.storage().instance().set(&symbol_short!("FL"), &fl_addr);
+    }
+
+    pub fn exec_op(e: Env) {
+        let token_client = token::Client::new(
+            &e,
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("T"))
+                .unwrap(),
+        );
+
+        let total_amount = (100 * STROOP) + compute_fee(&(100 * STROOP));
+
+        token_client.approve(
+            &e.current_contract_address(),
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("FL"))
+                .unwrap(),
+            &total_amount,
+            &(e.ledger().sequence() + 1),
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use soroban_sdk::{
+    symbol_short, testutils::{Address as _, Events}, vec, Address, Bytes, Env, Error, IntoVal,
+};
+
+mod factory {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_factory.wasm");
+}
+
+mod pool {
+    use soroban_sdk::contractimport;
+
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+// Tests that only allowed tokens get pools in allowlist mode
+// and that blocked tokens never do.
+#[test]
+fn test_allowlist_blocklist() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+    let token0 = env.register_stellar_asset_contract(Address::generate(&env));
+    let token1 = env.register_stellar_asset_contract(Address::generate(&env));
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    // any token can get a pool by default, unless blocked.
+    factory_client.set_blocked(&token1, &true);
+    assert!(factory_client.is_blocked(&token1));
+    assert!(!factory_client.can_deploy(&token1));
+    assert_eq!(
+        factory_client.try_deploy_canonical_pool(&token1),
+        Err(Ok(Error::from_contract_error(factory::Error::TokenNotAllowed as u32)))
+    );
+
+    factory_client.set_allowlist_mode(&true);
+    assert!(factory_client.allowlist_mode());
+    assert!(!factory_client.can_deploy(&token0));
+
+    factory_client.set_allowed(&token0, &true);
+    factory_client.set_allowed(&token1, &true);
+    assert!(factory_client.is_allowed(&token0));
+    factory_client.deploy_canonical_pool(&token0);
+
+    // blocked wins over allowed.
+    assert!(!factory_client.can_deploy(&token1));
+}
+
+// Tests that anyone can allow a Stellar classic asset by providing the asset
+// when classic assets are allowed by default.
+#[test]
+fn test_allow_stellar_asset() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let pool_wasm_hash = env.deployer().upload_contract_wasm(pool::WASM);
+
+    let protocol = Address::generate(&env);
+
+    // XDR of the native asset.
+    let native_asset = Bytes::from_array(&env, &[0, 0, 0, 0]);
+    let native_id = env.deployer().with_stellar_asset(native_asset.clone()).deploy();
+
+    let factory_id = env.register(factory::WASM, (protocol.clone(), pool_wasm_hash.clone()));
+    let factory_client = factory::Client::new(&env, &factory_id);
+
+    factory_client.set_allowlist_mode(&true);
+    factory_client.set_sac_allowed_by_default(&true);
+    let events = env.events().all();
+    assert_eq!(
+        events.slice(events.len() - 1..),
+        vec![&env, (factory_id.clone(), (symbol_short!("sacallow"),).into_val(&env), true.into_val(&env))]
+    );
+
+    assert_eq!(factory_client.allow_stellar_asset(&native_asset), native_id);
+    assert!(env.auths().is_empty());
+    assert!(factory_client.is_allowed(&native_id));
+
+    factory_client.deploy_canonical_pool(&native_id);
+}
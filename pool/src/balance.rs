@@ -1,24 +1,39 @@
-use crate::storage::{get_tot_supply, put_tot_supply, read_balance, write_balance};
-use core::ops::SubAssign;
+use crate::{
+    storage::{get_tot_supply, put_tot_supply, read_balance, write_balance},
+    types::Error,
+};
 use soroban_sdk::{Address, Env};
 
-pub(crate) fn mint_shares(e: &Env, to: Address, shares: i128) {
+pub(crate) fn mint_shares(e: &Env, to: Address, shares: i128) -> Result<(), Error> {
     // add to total supply
-    put_tot_supply(e, get_tot_supply(e) + shares);
+    let tot_supply = get_tot_supply(e)
+        .checked_add(shares)
+        .ok_or(Error::Overflow)?;
 
     // add to user balance
-    write_balance(e, to.clone(), read_balance(e, to) + shares);
+    let balance = read_balance(e, to.clone())
+        .checked_add(shares)
+        .ok_or(Error::Overflow)?;
+
+    put_tot_supply(e, tot_supply);
+    write_balance(e, to, balance);
+
+    Ok(())
 }
 
-// needs to be rewritten
-pub(crate) fn burn_shares(e: &Env, to: Address, shares: i128) {
+pub(crate) fn burn_shares(e: &Env, to: Address, shares: i128) -> Result<(), Error> {
     // update the total supply
-    let tot_supply = get_tot_supply(e);
-    put_tot_supply(e, tot_supply - shares);
-
-    let mut balance = read_balance(e, to.clone());
+    let tot_supply = get_tot_supply(e)
+        .checked_sub(shares)
+        .ok_or(Error::Overflow)?;
 
     // update addr's balance
-    balance.sub_assign(shares);
+    let balance = read_balance(e, to.clone())
+        .checked_sub(shares)
+        .ok_or(Error::Overflow)?;
+
+    put_tot_supply(e, tot_supply);
     write_balance(e, to, balance);
+
+    Ok(())
 }
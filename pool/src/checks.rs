@@ -1,15 +1,23 @@
-use soroban_sdk::{token::Client, Env};
-use crate::{storage::get_tot_supply, types::Error};
+use soroban_sdk::{token::Client, Address, Env};
+use crate::{insolvency::owed, lock::unlocked_balance, storage::{get_status, get_tot_supply, has_active_loan, is_borrowing, is_insolvent, is_paused, read_active_loan}, types::{Error, PoolStatus}, BPS_DENOMINATOR};
 
 // This function was introduced as an extra measure under the advice of auditors
 // in order to avoid potentially undesired events due to rounding errors.
 
-/// Extra-check that the pool balance never goes below the total supply.
-pub(crate) fn check_balance_ge_supply(env: &Env, token_client: &Client) -> Result<(), Error> {
-    let pool_balance = token_client.balance(&env.current_contract_address());
-    let total_supply = get_tot_supply(env);
+/// Extra-check that the pool can always pay back both the deposits and the fees it owes.
+/// The principal of a loan lent out through the factory counts towards the balance until it's settled.
+/// Insolvent pools pay out pro rata instead.
+pub(crate) fn check_solvency(env: &Env, token_client: &Client) -> Result<(), Error> {
+    if is_insolvent(env) {
+        return Ok(());
+    }
+
+    let mut pool_balance = token_client.balance(&env.current_contract_address());
+    if let Some(loan) = read_active_loan(env) {
+        pool_balance = pool_balance.checked_add(loan.amount).ok_or(Error::Overflow)?;
+    }
 
-    if pool_balance < total_supply {
+    if pool_balance < owed(env)? {
         return Err(Error::BalanceLtSupply)
     };
 
@@ -24,3 +32,87 @@ pub(crate) fn check_amount_gt_0(amount: i128) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Make sure that no loan is out, whether lent through the factory and pending
+/// settlement or borrowed directly, liquidity must not move while the principal is out of the pool.
+pub(crate) fn check_no_active_loan(env: &Env) -> Result<(), Error> {
+    if has_active_loan(env) || is_borrowing(env) {
+        return Err(Error::LoanInProgress);
+    }
+
+    Ok(())
+}
+
+/// Make sure that the fee rate is at most 100%
+pub(crate) fn check_fee_rate(fee_rate: u32) -> Result<(), Error> {
+    if fee_rate as i128 > BPS_DENOMINATOR {
+        return Err(Error::InvalidFeeRate);
+    }
+
+    Ok(())
+}
+
+/// Make sure that the pool still accepts deposits, i.e it's not deprecated nor sunset
+pub(crate) fn check_accepts_deposits(env: &Env) -> Result<(), Error> {
+    match get_status(env) {
+        PoolStatus::Active => Ok(()),
+        PoolStatus::Deprecated => Err(Error::PoolDeprecated),
+        PoolStatus::Sunset => Err(Error::PoolSunset),
+    }
+}
+
+/// Make sure that the pool still lends, i.e it's not sunset
+pub(crate) fn check_lends(env: &Env) -> Result<(), Error> {
+    if get_status(env) == PoolStatus::Sunset {
+        return Err(Error::PoolSunset);
+    }
+
+    Ok(())
+}
+
+/// Make sure that the pool has liquidity to lend, fees can't be distributed over a zero supply
+pub(crate) fn check_has_liquidity(env: &Env) -> Result<(), Error> {
+    if get_tot_supply(env) == 0 {
+        return Err(Error::NoLiquidity);
+    }
+
+    Ok(())
+}
+
+/// Make sure that the factory hasn't paused the pool, only withdrawals are open while paused
+pub(crate) fn check_not_paused(env: &Env) -> Result<(), Error> {
+    if is_paused(env) {
+        return Err(Error::Paused);
+    }
+
+    Ok(())
+}
+
+/// Make sure that `addr` isn't withdrawing shares which are still locked
+pub(crate) fn check_unlocked(env: &Env, addr: Address, amount: i128) -> Result<(), Error> {
+    if unlocked_balance(env, addr)? < amount {
+        return Err(Error::SharesLocked);
+    }
+
+    Ok(())
+}
+
+/// Make sure that the pool isn't in insolvency mode, in which it neither accepts deposits nor lends
+pub(crate) fn check_not_insolvent(env: &Env) -> Result<(), Error> {
+    if is_insolvent(env) {
+        return Err(Error::Insolvent);
+    }
+
+    Ok(())
+}
+
+/// Make sure that the pool isn't lending from within one of its own loans.
+/// A nested loan would be repaid while the outer principal is still out, so its
+/// fee would be distributed and counted towards the outer repayment.
+pub(crate) fn check_not_nested(env: &Env) -> Result<(), Error> {
+    if has_active_loan(env) || is_borrowing(env) {
+        return Err(Error::NestedLoan);
+    }
+
+    Ok(())
+}
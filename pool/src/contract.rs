@@ -1,7 +1,7 @@
 use crate::{
-    balance::{burn_shares, mint_shares}, checks::{check_amount_gt_0, check_balance_ge_supply}, compute_fee, events, execution::{invoke_receiver, invoke_receiver_moderc3156}, rewards::{pay_matured, update_rewards}, storage::*, token_utility::{get_token_client, transfer, transfer_in_pool, try_repay}, types::Error
+    balance::{burn_shares, mint_shares}, checks::{check_accepts_deposits, check_amount_gt_0, check_solvency, check_fee_rate, check_has_liquidity, check_lends, check_no_active_loan, check_not_insolvent, check_not_nested, check_not_paused, check_unlocked}, compute_fee, events, execution::{invoke_receiver, invoke_receiver_moderc3156}, incentives::{fund_incentive, pay_incentives}, insolvency::{apply_haircut, update_insolvency}, limits::{apply_loan_limits, validate_loan_limits}, lock::{effective_balance, lock_boost, lock_shares}, math::{scale_for_decimals, MAX_DECIMALS}, rewards::{pay_matured, update_fee_per_share_universal, update_rewards}, storage::*, token_utility::{get_token_client, transfer, transfer_in_pool, try_repay}, types::{ActiveLoan, Error, Incentive, LoanLimits, Lock, LockTier, PoolStatus}
 };
-use soroban_sdk::{contract, contractimpl, Address, Env};
+use soroban_sdk::{contract, contractimpl, panic_with_error, token, Address, Env, Vec};
 
 #[contract]
 pub struct Pool;
@@ -9,15 +9,39 @@ pub struct Pool;
 pub trait FlashLoanModErc3156 {
     /// The entry point for executing a flash loan, the initiator (or borrower) provides:
     /// `receiver_id: Address` The address of the receiver contract which contains the borrowing logic.
-    /// `amount` Amount of `token_id` to borrow (`token_id` is set when the contract is initialized).
+    /// `amount` Amount of `token_id` to borrow (`token_id` is set when the contract is deployed).
     fn borrow_erc(e: Env, initiator: Address, receiver_id: Address, amount: i128) -> Result<(), Error>;
 }
 
 pub trait FlashLoan {
     /// The entry point for executing a flash loan, the initiator (or borrower) provides:
     /// `receiver_id: Address` The address of the receiver contract which contains the borrowing logic.
-    /// `amount` Amount of `token_id` to borrow (`token_id` is set when the contract is initialized).
+    /// `amount` Amount of `token_id` to borrow (`token_id` is set when the contract is deployed).
+    /// Loans can't be nested: the pool doesn't lend while one of its loans is out.
     fn borrow(e: Env, receiver_id: Address, amount: i128) -> Result<(), Error>;
+
+    /// Returns the fee charged on loans in basis points.
+    fn fee_rate(e: Env) -> u32;
+
+    /// Returns the pool's token.
+    fn token(e: Env) -> Result<Address, Error>;
+}
+
+pub trait FactoryFlashLoan {
+    /// lend
+
+    /// Lends `amount` to `receiver_id` without invoking it, the factory is in charge of
+    /// invoking the receiver once all the assets of a batch have been lent out.
+    /// Can only be called by the factory the pool was deployed by.
+    /// Returns the fee that the receiver owes on top of `amount`.
+    fn lend(e: Env, receiver_id: Address, amount: i128) -> Result<i128, Error>;
+
+    /// settle
+
+    /// Checks that the loan lent out with `lend()` has been repaid with fees
+    /// by looking at the pool's balance, then distributes the fee to the liquidity providers.
+    /// Can only be called by the factory the pool was deployed by.
+    fn settle(e: Env) -> Result<(), Error>;
 }
 
 
@@ -28,12 +52,24 @@ pub trait Vault {
     /// This action currently must be authorized by the `admin`, so the proxy contract.
     /// This allows a pool to be only funded when the pool is part of the wider protocol, and is not an old pool.
     /// This design decision may be removed in the next release, follow https://github.com/xycloo/xycloans/issues/16
+    /// The minted shares are what the pool actually received, which is less than `amount` for tokens taxing transfers.
 
     /// `deposit()` must be provided with:
     /// `from: Address` Address of the liquidity provider.
     /// `amount: i128` Amount of `token_id` that `from` wants to deposit in the pool.
     fn deposit(env: Env, from: Address, amount: i128) -> Result<(), Error>;
 
+    /// deposit_for
+
+    /// Same as `deposit()`, but the minted shares are owned by `beneficiary`.
+    /// Only `from` needs to authorize the deposit.
+
+    /// `deposit_for()` must be provided with:
+    /// `from: Address` Address that funds the deposit.
+    /// `beneficiary: Address` Address that receives the liquidity provider shares.
+    /// `amount: i128` Amount of `token_id` that `from` wants to deposit in the pool.
+    fn deposit_for(env: Env, from: Address, beneficiary: Address, amount: i128) -> Result<(), Error>;
+
     /// update_fee_rewards
 
     /// Updates the matured rewards for a certain user `addr`
@@ -49,9 +85,20 @@ pub trait Vault {
     /// Before calling `withdraw_matured()` the user should call `update_fee_rewards`.
     /// If not, the matured fees that were not updated will not be lost, just not included in the payment.
 
+    /// Returns the amount paid out, less than the matured fees if the pool is insolvent.
+
     /// `withdraw_matured()` must be provided with:
     /// `addr: Address` The address that is withdrawing its fee rewards.
-    fn withdraw_matured(e: Env, addr: Address) -> Result<(), Error>;
+    fn withdraw_matured(e: Env, addr: Address) -> Result<i128, Error>;
+
+    /// withdraw_matured_to
+
+    /// Same as `withdraw_matured()`, but the matured fees of `addr` are paid to `recipient`.
+
+    /// `withdraw_matured_to()` must be provided with:
+    /// `addr: Address` The address that is withdrawing its fee rewards.
+    /// `recipient: Address` The address that receives the fee rewards.
+    fn withdraw_matured_to(e: Env, addr: Address, recipient: Address) -> Result<i128, Error>;
 
     /// withdraw
 
@@ -62,115 +109,430 @@ pub trait Vault {
     /// withdraw() must be provided with:
     /// `addr: Address` Address of the liquidity provider
     /// `amount: i28` Amount of shares that are being withdrawn
-    fn withdraw(env: Env, addr: Address, amount: i128) -> Result<(), Error>;
+    /// Returns the amount paid out, less than `amount` if the pool is insolvent.
+    fn withdraw(env: Env, addr: Address, amount: i128) -> Result<i128, Error>;
+
+    /// withdraw_to
+
+    /// Same as `withdraw()`, but the shares of `addr` are burned to pay `recipient`.
+
+    /// withdraw_to() must be provided with:
+    /// `addr: Address` Address of the liquidity provider
+    /// `recipient: Address` Address that receives the withdrawn liquidity
+    /// `amount: i28` Amount of shares that are being withdrawn
+    /// Returns the amount paid out, less than `amount` if the pool is insolvent.
+    fn withdraw_to(env: Env, addr: Address, recipient: Address, amount: i128) -> Result<i128, Error>;
+
+    /// exit
+
+    /// Closes the position of `addr`: updates its rewards, burns all of its shares
+    /// and pays out the liquidity together with the matured fees in a single transfer.
+    /// Unlike `withdraw_matured()`, doesn't fail when there are no matured fees.
+
+    /// exit() must be provided with:
+    /// `addr: Address` Address of the liquidity provider
+    /// Returns the total amount paid out.
+    fn exit(env: Env, addr: Address) -> Result<i128, Error>;
 
     /// Returns the amount of shares that an address holds.
     fn shares(e: Env, addr: Address) -> i128;
 
     /// Returns the amount of matured fees for an address.
     fn matured(env: Env, addr: Address) -> i128;
+
+    /// Returns the fees that the pool owes to the liquidity providers, matured or not.
+    /// The pool's balance always covers the total supply and these liabilities.
+    fn liabilities(env: Env) -> i128;
+}
+
+pub trait Locking {
+    /// deposit_locked
+
+    /// Same as `deposit()`, but the minted shares can't be withdrawn before the lock expires.
+    /// In exchange, locked shares weigh more in the fee distribution (see `boost()`).
+    /// Locks merge: the whole locked position of `from` is relocked for `tier`, which
+    /// must not expire before the existing lock.
+
+    /// `deposit_locked()` must be provided with:
+    /// `from: Address` Address of the liquidity provider.
+    /// `amount: i128` Amount of `token_id` that `from` wants to deposit in the pool.
+    /// `tier: LockTier` For how long the shares are locked.
+    fn deposit_locked(env: Env, from: Address, amount: i128, tier: LockTier) -> Result<(), Error>;
+
+    /// Returns the lock of an address, if any.
+    fn lock(env: Env, addr: Address) -> Option<Lock>;
+
+    /// Returns the weight of the shares locked for `tier` in basis points, unlocked shares weigh 10000.
+    fn boost(env: Env, tier: LockTier) -> u32;
+
+    /// release_lock
+
+    /// Updates the fee rewards of `addr` and releases its expired lock, from then on its
+    /// shares aren't boosted anymore.
+    /// This function may be called by anyone, so that boosts can be stopped as soon as locks expire.
+
+    /// `release_lock()` must be provided with:
+    /// `addr: Address` The address whose lock expired.
+    fn release_lock(env: Env, addr: Address) -> Result<(), Error>;
+
+    /// Returns the shares of an address as weighed in the fee distribution.
+    fn effective_shares(env: Env, addr: Address) -> Result<i128, Error>;
+}
+
+pub trait Incentives {
+    /// set_distributor
+
+    /// Approves or revokes an address allowed to fund incentive programs,
+    /// can only be called by the factory the pool was deployed by.
+    fn set_distributor(env: Env, distributor: Address, approved: bool) -> Result<(), Error>;
+
+    /// fund_incentive
+
+    /// Starts an incentive program paying `reward_token` to the liquidity providers, proportionally
+    /// to their effective shares. The distributor pays `rate` for every ledger until `end_ledger` upfront.
+    /// A reward token can only be funded again once its previous program has ended.
+
+    /// `fund_incentive()` must be provided with:
+    /// `distributor: Address` Approved distributor funding the program.
+    /// `reward_token: Address` Token distributed, must be different from the pool's token.
+    /// `rate: i128` Amount of `reward_token` distributed every ledger.
+    /// `end_ledger: u32` Ledger at which the distribution stops.
+    /// Returns the amount of `reward_token` paid by the distributor.
+    fn fund_incentive(env: Env, distributor: Address, reward_token: Address, rate: i128, end_ledger: u32) -> Result<i128, Error>;
+
+    /// claim_incentives
+
+    /// Updates the rewards of `addr` and pays out every reward token it accrued.
+    /// Returns the paid reward tokens with the amounts paid.
+    fn claim_incentives(env: Env, addr: Address) -> Result<Vec<(Address, i128)>, Error>;
+
+    /// Returns whether an address is an approved distributor.
+    fn is_distributor(env: Env, addr: Address) -> bool;
+
+    /// Returns the reward tokens that have been distributed by the pool.
+    fn reward_tokens(env: Env) -> Vec<Address>;
+
+    /// Returns the incentive program of a reward token, if any.
+    fn incentive(env: Env, reward_token: Address) -> Option<Incentive>;
+
+    /// Returns the amount of a reward token accrued by an address as of its last rewards update.
+    fn accrued_incentives(env: Env, addr: Address, reward_token: Address) -> i128;
+}
+
+pub trait Lifecycle {
+    /// set_status
+
+    /// Moves the pool through its lifecycle, can only be called by the factory the pool was deployed by.
+    /// Deprecated pools stop accepting deposits, sunset pools also stop lending.
+    /// Withdrawals are always open.
+    fn set_status(e: Env, status: PoolStatus) -> Result<(), Error>;
+
+    /// Returns the pool's lifecycle status.
+    fn status(e: Env) -> PoolStatus;
+
+    /// set_paused
+
+    /// Pauses or unpauses the pool in an emergency, can only be called by the factory the pool was deployed by.
+    /// Paused pools neither accept deposits nor lend, withdrawals stay open.
+    fn set_paused(e: Env, paused: bool) -> Result<(), Error>;
+
+    /// Returns whether the pool is paused.
+    fn paused(e: Env) -> bool;
+
+    /// set_loan_limits
+
+    /// Sets the limits on the pool's loans, can only be called by the factory the pool was deployed by.
+    /// Loans below `min_loan` or above `max_loan` are rejected, as well as loans which would bring
+    /// the volume lent out within the current ledger above `max_volume_per_ledger`.
+    fn set_loan_limits(e: Env, limits: LoanLimits) -> Result<(), Error>;
+
+    /// Returns the limits on the pool's loans.
+    fn loan_limits(e: Env) -> LoanLimits;
+
+    /// Returns the volume lent out within the current ledger, only tracked while it's limited.
+    fn ledger_volume(e: Env) -> i128;
+
+    /// Returns whether the pool is in insolvency mode, entered once its balance doesn't cover
+    /// the deposits and the fees it owes (e.g. after a clawback). Insolvent pools neither accept
+    /// deposits nor lend, and pay out withdrawals and matured fees pro rata to their balance.
+    /// The pool leaves insolvency mode on the next rewards update or withdrawal once its balance
+    /// covers what it owes again.
+    fn insolvent(e: Env) -> bool;
 }
 
 pub trait Initializable {
-    /// initialize
+    /// __constructor
 
-    /// Constructor function, only to be callable once
+    /// Constructor function, runs when the pool is deployed so the pool can never be front-run into a wrong configuration.
 
-    /// `initialize()` must be provided with:
-    /// `token_id: Address` The pool's token.
-    /// `flash_loan` The address of the associated flash loan contract. `flash_loan` should have `current_contract_address()` as `lp`.
-    fn initialize(env: Env, token: Address) -> Result<(), Error>;
+    /// `__constructor()` must be provided with:
+    /// `token: Address` The pool's token.
+    /// `fee_rate: u32` The fee charged on loans in basis points.
+    /// The token's decimals are read to pick the precision of the fee accounting.
+    /// `factory: Option<Address>` The factory that deployed the pool, if any. Only the factory can move the pool through
+    /// its lifecycle and lend out the pool's liquidity as part of a batch flash loan.
+    fn __constructor(env: Env, token: Address, fee_rate: u32, factory: Option<Address>);
 }
 
 #[contractimpl]
 impl Initializable for Pool {
-    fn initialize(env: Env, token: Address) -> Result<(), Error> {
-        if has_token_id(&env) {
-            return Err(Error::AlreadyInitialized);
+    fn __constructor(env: Env, token: Address, fee_rate: u32, factory: Option<Address>) {
+        if let Err(error) = check_fee_rate(fee_rate) {
+            panic_with_error!(&env, error);
+        }
+
+        // the fee accumulators are scaled to the token's precision.
+        let decimals = token::Client::new(&env, &token).decimals();
+        if decimals > MAX_DECIMALS {
+            panic_with_error!(&env, Error::UnsupportedDecimals);
         }
 
         put_token_id(&env, token);
-        Ok(())
+        put_fee_rate(&env, fee_rate);
+        put_scale(&env, scale_for_decimals(decimals));
+
+        if let Some(factory) = factory {
+            put_factory(&env, factory);
+        }
     }
 }
 
 #[contractimpl]
-impl Vault for Pool {
-    fn deposit(env: Env, from: Address, amount: i128) -> Result<(), Error> {
-        check_amount_gt_0(amount)?;
+impl Locking for Pool {
+    fn deposit_locked(env: Env, from: Address, amount: i128, tier: LockTier) -> Result<(), Error> {
+        let shares = deposit_shares(&env, &from, &from, amount)?;
+        lock_shares(&env, from.clone(), shares, tier)?;
 
-        from.require_auth();
+        events::deposited(&env, from, shares);
+        Ok(())
+    }
 
+    fn lock(env: Env, addr: Address) -> Option<Lock> {
+        read_lock(&env, addr)
+    }
+
+    fn boost(_env: Env, tier: LockTier) -> u32 {
+        lock_boost(tier)
+    }
+
+    fn release_lock(env: Env, addr: Address) -> Result<(), Error> {
         bump_instance(&env);
 
-        // we update the rewards before the deposit to avoid the abuse of the collected fees by withdrawing them with liquidity that didn't contribute to their generation.
-        update_rewards(&env, from.clone());
+        match read_lock(&env, addr.clone()) {
+            Some(lock) if env.ledger().timestamp() >= lock.unlock => {}
+            _ => return Err(Error::InvalidLock),
+        }
 
-        // transfer the funds into the flash loan
-        let token_client = get_token_client(&env);
-        transfer_in_pool(&env, &token_client, &from, &amount);
-        //transfer_into_flash_loan(&e, &token_client, &from, &amount);
+        // the lock is released once the boosted rewards are accounted for.
+        update_rewards(&env, addr)
+    }
+
+    fn effective_shares(env: Env, addr: Address) -> Result<i128, Error> {
+        effective_balance(&env, addr)
+    }
+}
 
-        // mint the new shares to the lender.
-        // shares to mint will always be the amount deposited, see https://github.com/xycloo/xycloans/issues/17
-        mint_shares(&env, from.clone(), amount);
+#[contractimpl]
+impl Incentives for Pool {
+    fn set_distributor(env: Env, distributor: Address, approved: bool) -> Result<(), Error> {
+        get_factory(&env)?.require_auth();
 
-        events::deposited(&env, from, amount);
+        bump_instance(&env);
+
+        write_distributor(&env, distributor.clone(), approved);
+        events::distributor_set(&env, distributor, approved);
         Ok(())
     }
 
-    fn withdraw_matured(env: Env, addr: Address) -> Result<(), Error> {
-        // require lender auth for withdrawal
+    fn fund_incentive(env: Env, distributor: Address, reward_token: Address, rate: i128, end_ledger: u32) -> Result<i128, Error> {
+        distributor.require_auth();
+
+        bump_instance(&env);
+
+        let amount = fund_incentive(&env, distributor, reward_token.clone(), rate, end_ledger)?;
+
+        events::incentive_funded(&env, reward_token, rate, end_ledger, amount);
+        Ok(amount)
+    }
+
+    fn claim_incentives(env: Env, addr: Address) -> Result<Vec<(Address, i128)>, Error> {
         addr.require_auth();
 
         bump_instance(&env);
 
-        // pay the matured yield
-        let paid = pay_matured(&env, addr.clone())?;
+        update_rewards(&env, addr.clone())?;
+        let payouts = pay_incentives(&env, addr.clone());
 
-        // ensure that the pool's balance is >= total supply
-        check_balance_ge_supply(&env, &get_token_client(&env))?;
+        // the pool's token doesn't move here, a shortfall can only come from outside the pool
+        update_insolvency(&env, &get_token_client(&env))?;
 
-        events::matured_withdrawn(&env, addr, paid);
+        events::incentives_claimed(&env, addr, payouts.clone());
+        Ok(payouts)
+    }
+
+    fn is_distributor(env: Env, addr: Address) -> bool {
+        is_distributor(&env, addr)
+    }
+
+    fn reward_tokens(env: Env) -> Vec<Address> {
+        get_reward_tokens(&env)
+    }
+
+    fn incentive(env: Env, reward_token: Address) -> Option<Incentive> {
+        read_incentive(&env, reward_token)
+    }
+
+    fn accrued_incentives(env: Env, addr: Address, reward_token: Address) -> i128 {
+        read_incentives_accrued(&env, addr, reward_token)
+    }
+}
+
+#[contractimpl]
+impl Lifecycle for Pool {
+    fn set_status(e: Env, status: PoolStatus) -> Result<(), Error> {
+        get_factory(&e)?.require_auth();
+
+        bump_instance(&e);
+
+        put_status(&e, status);
+        events::status_changed(&e, status);
+        Ok(())
+    }
+
+    fn status(e: Env) -> PoolStatus {
+        get_status(&e)
+    }
+
+    fn set_paused(e: Env, paused: bool) -> Result<(), Error> {
+        get_factory(&e)?.require_auth();
+
+        bump_instance(&e);
+
+        put_paused(&e, paused);
+        events::paused(&e, paused);
         Ok(())
     }
 
+    fn paused(e: Env) -> bool {
+        is_paused(&e)
+    }
+
+    fn set_loan_limits(e: Env, limits: LoanLimits) -> Result<(), Error> {
+        get_factory(&e)?.require_auth();
+        validate_loan_limits(&limits)?;
+
+        bump_instance(&e);
+
+        put_loan_limits(&e, &limits);
+        events::loan_limits_set(&e, limits);
+        Ok(())
+    }
+
+    fn loan_limits(e: Env) -> LoanLimits {
+        get_loan_limits(&e)
+    }
+
+    fn ledger_volume(e: Env) -> i128 {
+        read_volume(&e)
+    }
+
+    fn insolvent(e: Env) -> bool {
+        is_insolvent(&e)
+    }
+}
+
+#[contractimpl]
+impl Vault for Pool {
+    fn deposit(env: Env, from: Address, amount: i128) -> Result<(), Error> {
+        let shares = deposit_shares(&env, &from, &from, amount)?;
+
+        events::deposited(&env, from, shares);
+        Ok(())
+    }
+
+    fn deposit_for(env: Env, from: Address, beneficiary: Address, amount: i128) -> Result<(), Error> {
+        let shares = deposit_shares(&env, &from, &beneficiary, amount)?;
+
+        events::deposited_for(&env, from, beneficiary, shares);
+        Ok(())
+    }
+
+    fn withdraw_matured(env: Env, addr: Address) -> Result<i128, Error> {
+        let paid = withdraw_fees(&env, &addr, &addr)?;
+
+        events::matured_withdrawn(&env, addr, paid);
+        Ok(paid)
+    }
+
+    fn withdraw_matured_to(env: Env, addr: Address, recipient: Address) -> Result<i128, Error> {
+        let paid = withdraw_fees(&env, &addr, &recipient)?;
+
+        events::matured_withdrawn_to(&env, addr, recipient, paid);
+        Ok(paid)
+    }
+
     fn update_fee_rewards(env: Env, addr: Address) -> Result<(), Error> {
         bump_instance(&env);
 
-        update_rewards(&env, addr);
+        update_rewards(&env, addr)?;
+
+        // no funds move here, a shortfall can only come from outside the pool
+        update_insolvency(&env, &get_token_client(&env))?;
 
         Ok(())
     }
 
-    fn withdraw(env: Env, addr: Address, amount: i128) -> Result<(), Error> {
-        check_amount_gt_0(amount)?;
-        
+    fn withdraw(env: Env, addr: Address, amount: i128) -> Result<i128, Error> {
+        let paid = withdraw_shares(&env, &addr, &addr, amount)?;
+
+        events::withdrawn(&env, addr, amount, paid);
+        Ok(paid)
+    }
+
+    fn withdraw_to(env: Env, addr: Address, recipient: Address, amount: i128) -> Result<i128, Error> {
+        let paid = withdraw_shares(&env, &addr, &recipient, amount)?;
+
+        events::withdrawn_to(&env, addr, recipient, amount, paid);
+        Ok(paid)
+    }
+
+    fn exit(env: Env, addr: Address) -> Result<i128, Error> {
         // require lender auth for withdrawal
         addr.require_auth();
+        check_no_active_loan(&env)?;
 
         bump_instance(&env);
 
-        let addr_balance = read_balance(&env, addr.clone());
+        // update addr's rewards so that the fees matured by the whole balance are paid out
+        update_rewards(&env, addr.clone())?;
 
-        // if the desired burned shares are more than the lender's balance return an error
-        // if the amount is 0 return an error
-        if addr_balance < amount || amount == 0 {
+        let shares = read_balance(&env, addr.clone());
+        check_unlocked(&env, addr.clone(), shares)?;
+
+        let matured = read_matured_fees_particular(&env, addr.clone());
+        if shares == 0 && matured == 0 {
             return Err(Error::InvalidShareBalance);
         }
 
-        // update addr's rewards
-        update_rewards(&env, addr.clone());
-
-        // pay out the corresponding deposit
+        // pay out the deposit and the matured yield at once, with a haircut if the pool can't pay everyone
         let token_client = get_token_client(&env);
-        transfer(&env, &token_client, &addr, &amount);
-
-        // burn the shares
-        burn_shares(&env, addr.clone(), amount);
-
-        events::withdrawn(&env, addr, amount);
-        Ok(())
+        update_insolvency(&env, &token_client)?;
+        let owed = shares.checked_add(matured).ok_or(Error::Overflow)?;
+        let paid = apply_haircut(&env, &token_client, owed)?;
+        let liabilities = get_liabilities(&env)
+            .checked_sub(matured)
+            .ok_or(Error::Overflow)?;
+        transfer(&env, &token_client, &addr, &paid);
+
+        burn_shares(&env, addr.clone(), shares)?;
+        write_matured_fees_particular(&env, addr.clone(), 0);
+        put_liabilities(&env, liabilities);
+
+        // ensure that the pool can still pay everyone
+        check_solvency(&env, &token_client)?;
+
+        events::exited(&env, addr, shares, paid);
+        Ok(paid)
     }
 
     fn shares(e: Env, addr: Address) -> i128 {
@@ -180,6 +542,95 @@ impl Vault for Pool {
     fn matured(env: Env, addr: Address) -> i128 {
         read_matured_fees_particular(&env, addr)
     }
+
+    fn liabilities(env: Env) -> i128 {
+        get_liabilities(&env)
+    }
+}
+
+// `from` funds the deposit, the shares are minted to `beneficiary`.
+// Returns the minted shares, i.e what the pool actually received.
+fn deposit_shares(env: &Env, from: &Address, beneficiary: &Address, amount: i128) -> Result<i128, Error> {
+    check_amount_gt_0(amount)?;
+
+    from.require_auth();
+    check_no_active_loan(env)?;
+    check_not_paused(env)?;
+    check_not_insolvent(env)?;
+    check_accepts_deposits(env)?;
+
+    bump_instance(env);
+
+    // we update the rewards before the deposit to avoid the abuse of the collected fees by withdrawing them with liquidity that didn't contribute to their generation.
+    update_rewards(env, beneficiary.clone())?;
+
+    // transfer the funds into the flash loan
+    let token_client = get_token_client(env);
+    let received = transfer_in_pool(env, &token_client, from, &amount)?;
+
+    // mint the new shares to the lender.
+    // shares to mint will always be the amount received, see https://github.com/xycloo/xycloans/issues/17
+    mint_shares(env, beneficiary.clone(), received)?;
+
+    // ensure that the pool can still pay everyone
+    check_solvency(env, &token_client)?;
+
+    Ok(received)
+}
+
+// the matured fees of `addr` are paid to `recipient`.
+fn withdraw_fees(env: &Env, addr: &Address, recipient: &Address) -> Result<i128, Error> {
+    // require lender auth for withdrawal
+    addr.require_auth();
+    check_no_active_loan(env)?;
+
+    bump_instance(env);
+
+    // pay the matured yield, with a haircut if the pool can't pay everyone
+    update_insolvency(env, &get_token_client(env))?;
+    let paid = pay_matured(env, addr.clone(), recipient)?;
+
+    // ensure that the pool can still pay everyone
+    check_solvency(env, &get_token_client(env))?;
+
+    Ok(paid)
+}
+
+// the shares of `addr` are burned, the liquidity is paid to `recipient`.
+fn withdraw_shares(env: &Env, addr: &Address, recipient: &Address, amount: i128) -> Result<i128, Error> {
+    check_amount_gt_0(amount)?;
+
+    // require lender auth for withdrawal
+    addr.require_auth();
+    check_no_active_loan(env)?;
+
+    bump_instance(env);
+
+    let addr_balance = read_balance(env, addr.clone());
+
+    // if the desired burned shares are more than the lender's balance return an error
+    // if the amount is 0 return an error
+    if addr_balance < amount || amount == 0 {
+        return Err(Error::InvalidShareBalance);
+    }
+
+    // update addr's rewards
+    update_rewards(env, addr.clone())?;
+    check_unlocked(env, addr.clone(), amount)?;
+
+    // pay out the corresponding deposit, with a haircut if the pool can't pay everyone
+    let token_client = get_token_client(env);
+    update_insolvency(env, &token_client)?;
+    let paid = apply_haircut(env, &token_client, amount)?;
+    transfer(env, &token_client, recipient, &paid);
+
+    // burn the shares
+    burn_shares(env, addr.clone(), amount)?;
+
+    // ensure that the pool can still pay everyone
+    check_solvency(env, &token_client)?;
+
+    Ok(paid)
 }
 
 #[cfg(feature="moderc3156")]
@@ -188,21 +639,35 @@ impl FlashLoanModErc3156 for Pool {
     fn borrow_erc(env: Env, initiator: Address, receiver_id: Address, amount: i128) -> Result<(), Error> {
         initiator.require_auth();
         check_amount_gt_0(amount)?;
+        check_not_nested(&env)?;
+        check_not_paused(&env)?;
+        check_not_insolvent(&env)?;
+        check_lends(&env)?;
+        check_has_liquidity(&env)?;
         
         bump_instance(&env);
 
         let client = get_token_client(&env);
+        apply_loan_limits(&env, &client, amount)?;
+
+        // compute the fee before lending, loans whose fee doesn't fit are rejected
+        let fee = compute_fee(&amount, get_fee_rate(&env))?;
+
+        // the loan is flagged until it's repaid
+        write_borrowing(&env);
 
         // transfer `amount` to `receiver_id`
         transfer(&env, &client, &receiver_id, &amount);
 
         // invoke the `exec_op()` function of the receiver contract
-        let fee = compute_fee(&amount);
-
         invoke_receiver_moderc3156(&env, &receiver_id, &client.address, &amount, &fee);
 
         // try `transfer_from()` of (`amount` + fees) from the receiver to the flash loan
         try_repay(&env, &client, &receiver_id, amount, fee)?;
+        remove_borrowing(&env);
+
+        // ensure that the pool can still pay everyone
+        check_solvency(&env, &client)?;
 
         events::loan_successful(&env, receiver_id, amount);
         Ok(())
@@ -214,23 +679,110 @@ impl FlashLoanModErc3156 for Pool {
 impl FlashLoan for Pool {
     fn borrow(env: Env, receiver_id: Address, amount: i128) -> Result<(), Error> {
         check_amount_gt_0(amount)?;
+        check_not_nested(&env)?;
+        check_not_paused(&env)?;
+        check_not_insolvent(&env)?;
+        check_lends(&env)?;
+        check_has_liquidity(&env)?;
 
         bump_instance(&env);
 
         let client = get_token_client(&env);
+        apply_loan_limits(&env, &client, amount)?;
+
+        // compute the fee before lending, loans whose fee doesn't fit are rejected
+        let fee = compute_fee(&amount, get_fee_rate(&env))?;
+
+        // the loan is flagged until it's repaid
+        write_borrowing(&env);
 
         // transfer `amount` to `receiver_id`
         transfer(&env, &client, &receiver_id, &amount);
 
         // invoke the `exec_op()` function of the receiver contract
-        let fee = compute_fee(&amount);
-
         invoke_receiver(&env, &receiver_id);
 
         // try `transfer_from()` of (`amount` + fees) from the receiver to the flash loan
         try_repay(&env, &client, &receiver_id, amount, fee)?;
+        remove_borrowing(&env);
+
+        // ensure that the pool can still pay everyone
+        check_solvency(&env, &client)?;
 
         events::loan_successful(&env, receiver_id, amount);
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn fee_rate(env: Env) -> u32 {
+        get_fee_rate(&env)
+    }
+
+    fn token(env: Env) -> Result<Address, Error> {
+        get_token_id(&env)
+    }
+}
+#[contractimpl]
+impl FactoryFlashLoan for Pool {
+    fn lend(env: Env, receiver_id: Address, amount: i128) -> Result<i128, Error> {
+        check_amount_gt_0(amount)?;
+        get_factory(&env)?.require_auth();
+        check_no_active_loan(&env)?;
+        check_not_nested(&env)?;
+        check_not_paused(&env)?;
+        check_not_insolvent(&env)?;
+        check_lends(&env)?;
+        check_has_liquidity(&env)?;
+
+        bump_instance(&env);
+
+        let client = get_token_client(&env);
+        apply_loan_limits(&env, &client, amount)?;
+        let fee = compute_fee(&amount, get_fee_rate(&env))?;
+
+        // record the balance before lending, repayment is checked against it.
+        write_active_loan(&env, &ActiveLoan {
+            receiver: receiver_id.clone(),
+            amount,
+            fee,
+            balance: client.balance(&env.current_contract_address()),
+        });
+
+        // transfer `amount` to `receiver_id`
+        transfer(&env, &client, &receiver_id, &amount);
+
+        // ensure that the pool can still pay everyone
+        check_solvency(&env, &client)?;
+
+        Ok(fee)
+    }
+
+    fn settle(env: Env) -> Result<(), Error> {
+        get_factory(&env)?.require_auth();
+
+        bump_instance(&env);
+
+        let loan = if let Some(loan) = read_active_loan(&env) {
+            loan
+        } else {
+            return Err(Error::NoActiveLoan);
+        };
+
+        // the factory moves (`amount` + fees) back into the pool before settling
+        let client = get_token_client(&env);
+        let repaid = loan.balance.checked_add(loan.fee).ok_or(Error::Overflow)?;
+        if client.balance(&env.current_contract_address()) < repaid {
+            return Err(Error::LoanNotRepaid);
+        }
+
+        remove_active_loan(&env);
+
+        // loan is now repaid with interest.
+        update_fee_per_share_universal(&env, loan.fee)?;
+
+        // ensure that the pool can still pay everyone
+        check_solvency(&env, &client)?;
+
+        events::loan_successful(&env, loan.receiver, loan.amount);
+        Ok(())
+    }
+}
@@ -1,26 +1,98 @@
-use soroban_sdk::{symbol_short, Address, Env};
+use soroban_sdk::{symbol_short, Address, Env, Vec};
+
+use crate::types::{LoanLimits, Lock, PoolStatus};
 
 pub(crate) fn deposited(env: &Env, from: Address, amount: i128) {
     let topics = (symbol_short!("deposit"), from);
     env.events().publish(topics, amount);
 }
 
+pub(crate) fn deposited_for(env: &Env, from: Address, beneficiary: Address, amount: i128) {
+    let topics = (symbol_short!("depositfr"), from, beneficiary);
+    env.events().publish(topics, amount);
+}
+
+pub(crate) fn locked(env: &Env, addr: Address, lock: Lock) {
+    let topics = (symbol_short!("locked"), addr);
+    env.events().publish(topics, lock);
+}
+
+pub(crate) fn lock_released(env: &Env, addr: Address, shares: i128) {
+    let topics = (symbol_short!("released"), addr);
+    env.events().publish(topics, shares);
+}
+
 pub(crate) fn matured_withdrawn(env: &Env, addr: Address, withdrawn: i128) {
     let topics = (symbol_short!("collect"), addr);
     env.events().publish(topics, withdrawn);
 }
 
+pub(crate) fn matured_withdrawn_to(env: &Env, addr: Address, recipient: Address, withdrawn: i128) {
+    let topics = (symbol_short!("collectto"), addr, recipient);
+    env.events().publish(topics, withdrawn);
+}
+
 pub(crate) fn new_fees(env: &Env, addr: Address, matured: i128) {
     let topics = (symbol_short!("newfee"), addr);
     env.events().publish(topics, matured);
 }
 
-pub(crate) fn withdrawn(env: &Env, from: Address, amount: i128) {
+pub(crate) fn withdrawn(env: &Env, from: Address, shares: i128, paid: i128) {
     let topics = (symbol_short!("withdrawn"), from);
-    env.events().publish(topics, amount);
+    env.events().publish(topics, (shares, paid));
+}
+
+pub(crate) fn withdrawn_to(env: &Env, from: Address, recipient: Address, shares: i128, paid: i128) {
+    let topics = (symbol_short!("withdrwto"), from, recipient);
+    env.events().publish(topics, (shares, paid));
+}
+
+pub(crate) fn exited(env: &Env, addr: Address, shares: i128, paid: i128) {
+    let topics = (symbol_short!("exit"), addr);
+    env.events().publish(topics, (shares, paid));
 }
 
 pub(crate) fn loan_successful(env: &Env, receiver_contract: Address, amount: i128) {
     let topics = (symbol_short!("borrow"), receiver_contract);
     env.events().publish(topics, amount);
 }
+
+pub(crate) fn status_changed(env: &Env, status: PoolStatus) {
+    let topics = (symbol_short!("status"), );
+    env.events().publish(topics, status);
+}
+
+pub(crate) fn insolvent(env: &Env, insolvent: bool, balance: i128, owed: i128) {
+    let topics = (symbol_short!("insolvent"), );
+    env.events().publish(topics, (insolvent, balance, owed));
+}
+
+pub(crate) fn paused(env: &Env, paused: bool) {
+    let topics = (symbol_short!("paused"), );
+    env.events().publish(topics, paused);
+}
+
+pub(crate) fn loan_limits_set(env: &Env, limits: LoanLimits) {
+    let topics = (symbol_short!("limitsset"), );
+    env.events().publish(topics, limits);
+}
+
+pub(crate) fn distributor_set(env: &Env, distributor: Address, approved: bool) {
+    let topics = (symbol_short!("distrib"), distributor);
+    env.events().publish(topics, approved);
+}
+
+pub(crate) fn incentive_funded(env: &Env, reward_token: Address, rate: i128, end_ledger: u32, amount: i128) {
+    let topics = (symbol_short!("incentive"), reward_token);
+    env.events().publish(topics, (rate, end_ledger, amount));
+}
+
+pub(crate) fn incentive_stopped(env: &Env, reward_token: Address, ledger: u32) {
+    let topics = (symbol_short!("incstop"), reward_token);
+    env.events().publish(topics, ledger);
+}
+
+pub(crate) fn incentives_claimed(env: &Env, addr: Address, payouts: Vec<(Address, i128)>) {
+    let topics = (symbol_short!("claiminc"), addr);
+    env.events().publish(topics, payouts);
+}
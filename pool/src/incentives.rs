@@ -0,0 +1,134 @@
+use core::cmp::min;
+use soroban_sdk::{token, unwrap::UnwrapOptimized, vec, Address, Env, Vec};
+
+use crate::{
+    events,
+    math::{compute_fee_earned, compute_fee_per_share},
+    storage::*,
+    types::{Error, Incentive},
+};
+
+/// Every reward token is checkpointed on each `update_rewards()`, so their number is capped.
+pub(crate) const MAX_REWARD_TOKENS: u32 = 5;
+
+// Accrues what was distributed since the last checkpoint over the effective supply.
+// Rewards distributed while the pool has no liquidity aren't accounted to anyone.
+// A program whose accounting overflows is stopped instead of failing, reward tokens
+// must never block the withdrawals of the liquidity providers.
+fn checkpoint_incentive(e: &Env, reward_token: Address) -> Incentive {
+    let mut incentive = read_incentive(e, reward_token.clone()).unwrap_optimized();
+    let now = min(e.ledger().sequence(), incentive.end_ledger);
+
+    if now > incentive.last_ledger {
+        let supply = get_tot_supply(e).checked_add(get_boost_supply(e));
+        if supply != Some(0) {
+            let reward_per_share = supply.ok_or(Error::Overflow).and_then(|supply| {
+                let distributed = incentive
+                    .rate
+                    .checked_mul((now - incentive.last_ledger) as i128)
+                    .ok_or(Error::Overflow)?;
+                // what can't be distributed because of rounding is left in the pool.
+                compute_fee_per_share(e, incentive.reward_per_share, distributed, supply, get_scale(e))
+            });
+
+            match reward_per_share {
+                Ok((reward_per_share, _)) => incentive.reward_per_share = reward_per_share,
+                Err(_) => {
+                    incentive.end_ledger = now;
+                    events::incentive_stopped(e, reward_token.clone(), now);
+                }
+            }
+        }
+
+        incentive.last_ledger = now;
+        write_incentive(e, reward_token, &incentive);
+    }
+
+    incentive
+}
+
+/// Checkpoints every reward token and accrues what `addr` earned with `effective_balance`.
+/// Rewards which don't fit an `i128` can't be paid out anyway, they're capped rather than failing.
+pub(crate) fn update_incentives(e: &Env, addr: Address, effective_balance: i128) {
+    for reward_token in get_reward_tokens(e).iter() {
+        let incentive = checkpoint_incentive(e, reward_token.clone());
+        let paid = read_incentive_per_share_paid(e, addr.clone(), reward_token.clone());
+
+        if incentive.reward_per_share == paid {
+            continue;
+        }
+
+        let earned = compute_fee_earned(e, effective_balance, incentive.reward_per_share, paid, get_scale(e))
+            .unwrap_or(i128::MAX);
+        let accrued = read_incentives_accrued(e, addr.clone(), reward_token.clone()).saturating_add(earned);
+
+        write_incentive_per_share_paid(e, addr.clone(), reward_token.clone(), incentive.reward_per_share);
+        write_incentives_accrued(e, addr.clone(), reward_token, accrued);
+    }
+}
+
+// A reward token can only be funded again once its previous program has ended.
+pub(crate) fn fund_incentive(e: &Env, distributor: Address, reward_token: Address, rate: i128, end_ledger: u32) -> Result<i128, Error> {
+    if !is_distributor(e, distributor.clone()) {
+        return Err(Error::NotDistributor);
+    }
+
+    if reward_token == get_token_id(e)? {
+        return Err(Error::InvalidRewardToken);
+    }
+
+    let now = e.ledger().sequence();
+    if rate <= 0 || end_ledger <= now {
+        return Err(Error::InvalidIncentive);
+    }
+
+    let mut reward_tokens = get_reward_tokens(e);
+    let mut incentive = if reward_tokens.contains(&reward_token) {
+        let incentive = checkpoint_incentive(e, reward_token.clone());
+        if incentive.end_ledger > now {
+            return Err(Error::IncentiveActive);
+        }
+
+        incentive
+    } else {
+        if reward_tokens.len() >= MAX_REWARD_TOKENS {
+            return Err(Error::TooManyRewardTokens);
+        }
+
+        reward_tokens.push_back(reward_token.clone());
+        put_reward_tokens(e, &reward_tokens);
+
+        Incentive { rate: 0, end_ledger: now, last_ledger: now, reward_per_share: 0 }
+    };
+
+    let amount = rate
+        .checked_mul((end_ledger - now) as i128)
+        .ok_or(Error::InvalidIncentive)?;
+    token::Client::new(e, &reward_token).transfer(&distributor, &e.current_contract_address(), &amount);
+
+    incentive.rate = rate;
+    incentive.end_ledger = end_ledger;
+    incentive.last_ledger = now;
+    write_incentive(e, reward_token, &incentive);
+
+    Ok(amount)
+}
+
+/// Pays out every reward token accrued by `addr`, the rewards must be updated beforehand.
+pub(crate) fn pay_incentives(e: &Env, addr: Address) -> Vec<(Address, i128)> {
+    let mut payouts = vec![e];
+
+    for reward_token in get_reward_tokens(e).iter() {
+        let accrued = read_incentives_accrued(e, addr.clone(), reward_token.clone());
+        if accrued == 0 {
+            continue;
+        }
+
+        write_incentives_accrued(e, addr.clone(), reward_token.clone(), 0);
+        token::Client::new(e, &reward_token).transfer(&e.current_contract_address(), &addr, &accrued);
+
+        payouts.push_back((reward_token, accrued));
+    }
+
+    payouts
+}
@@ -0,0 +1,54 @@
+use fixed_point_math::FixedPoint;
+use soroban_sdk::{token, Env};
+
+use crate::{
+    events,
+    storage::{get_liabilities, get_tot_supply, is_insolvent, put_insolvent, read_active_loan},
+    types::Error,
+};
+
+/// What the pool owes to its liquidity providers: their deposits and matured fees.
+pub(crate) fn owed(env: &Env) -> Result<i128, Error> {
+    get_tot_supply(env)
+        .checked_add(get_liabilities(env))
+        .ok_or(Error::Overflow)
+}
+
+/// Enters the insolvency mode when the pool's balance doesn't cover the deposits
+/// and the fees it owes anymore, e.g. after the issuer clawed back part of it.
+/// The pool leaves it once its balance covers them again, e.g. after a top-up.
+pub(crate) fn update_insolvency(env: &Env, token_client: &token::Client) -> Result<(), Error> {
+    // the principal of a loan lent out through the factory isn't lost until settlement fails.
+    let mut balance = token_client.balance(&env.current_contract_address());
+    if let Some(loan) = read_active_loan(env) {
+        balance = balance.checked_add(loan.amount).ok_or(Error::Overflow)?;
+    }
+
+    let owed = owed(env)?;
+
+    let insolvent = balance < owed;
+    if insolvent != is_insolvent(env) {
+        put_insolvent(env, insolvent);
+        events::insolvent(env, insolvent, balance, owed);
+    }
+
+    Ok(())
+}
+
+/// In insolvency mode everything the pool owes is paid pro rata to its balance,
+/// so that the loss is shared by all liquidity providers instead of the last to withdraw.
+/// Must be called before the paid shares and fees are removed from the pool's accounting.
+pub(crate) fn apply_haircut(env: &Env, token_client: &token::Client, amount: i128) -> Result<i128, Error> {
+    if !is_insolvent(env) {
+        return Ok(amount);
+    }
+
+    let balance = token_client.balance(&env.current_contract_address());
+    let owed = owed(env)?;
+
+    if balance >= owed {
+        return Ok(amount);
+    }
+
+    amount.fixed_mul_floor(balance, owed).ok_or(Error::Overflow)
+}
@@ -6,6 +6,10 @@ mod balance;
 pub mod contract;
 mod events;
 mod execution;
+mod incentives;
+mod insolvency;
+mod limits;
+mod lock;
 pub mod math;
 mod rewards;
 mod storage;
@@ -13,10 +17,15 @@ mod token_utility;
 mod types;
 mod checks;
 
-pub fn compute_fee(amount: &i128) -> i128 {
-    amount.fixed_div_ceil(1250_0000000, 10_000_000).unwrap() // 0.08%, still TBD
+/// Computes the fee on a loan of `amount`, `fee_rate` is expressed in basis points.
+pub fn compute_fee(amount: &i128, fee_rate: u32) -> Result<i128, types::Error> {
+    amount
+        .fixed_mul_ceil(fee_rate as i128, BPS_DENOMINATOR)
+        .ok_or(types::Error::Overflow)
 }
 
+pub(crate) const BPS_DENOMINATOR: i128 = 10_000;
+
 // These numbers are conservative but considering the SACs numbers (SACs are much more likely to be invoked)
 // they seem reasonable.
 
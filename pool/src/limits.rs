@@ -0,0 +1,63 @@
+use fixed_point_math::FixedPoint;
+use soroban_sdk::{token, Env};
+
+use crate::{
+    storage::{get_loan_limits, read_volume, write_volume},
+    types::{Error, LoanLimits, MaxLoan},
+    BPS_DENOMINATOR,
+};
+
+/// Make sure that the limits are consistent, a maximum loan can't be 0 nor below the minimum loan.
+pub(crate) fn validate_loan_limits(limits: &LoanLimits) -> Result<(), Error> {
+    if limits.min_loan < 0 || limits.max_volume_per_ledger < 0 {
+        return Err(Error::InvalidLoanLimits);
+    }
+
+    match limits.max_loan {
+        MaxLoan::Unlimited => Ok(()),
+        MaxLoan::Amount(max) if max > 0 && max >= limits.min_loan => Ok(()),
+        MaxLoan::Share(share) if share > 0 && share as i128 <= BPS_DENOMINATOR => Ok(()),
+        _ => Err(Error::InvalidLoanLimits),
+    }
+}
+
+// The largest loan as a share of the liquidity is measured on the pool's balance before lending.
+fn max_loan(env: &Env, token_client: &token::Client, max_loan: MaxLoan) -> Result<Option<i128>, Error> {
+    match max_loan {
+        MaxLoan::Unlimited => Ok(None),
+        MaxLoan::Amount(max) => Ok(Some(max)),
+        MaxLoan::Share(share) => token_client
+            .balance(&env.current_contract_address())
+            .fixed_mul_floor(share as i128, BPS_DENOMINATOR)
+            .map(Some)
+            .ok_or(Error::Overflow),
+    }
+}
+
+/// Checks a loan of `amount` against the pool's limits and counts it towards the volume of the ledger.
+/// Must be called before the principal is lent out.
+pub(crate) fn apply_loan_limits(env: &Env, token_client: &token::Client, amount: i128) -> Result<(), Error> {
+    let limits = get_loan_limits(env);
+
+    if amount < limits.min_loan {
+        return Err(Error::LoanTooSmall);
+    }
+
+    if let Some(max) = max_loan(env, token_client, limits.max_loan)? {
+        if amount > max {
+            return Err(Error::LoanTooLarge);
+        }
+    }
+
+    // the volume is only tracked while it's limited.
+    if limits.max_volume_per_ledger > 0 {
+        let volume = read_volume(env).checked_add(amount).ok_or(Error::Overflow)?;
+        if volume > limits.max_volume_per_ledger {
+            return Err(Error::VolumeExceeded);
+        }
+
+        write_volume(env, volume);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use fixed_point_math::FixedPoint;
+use soroban_sdk::{Address, Env};
+
+use crate::{
+    events,
+    storage::{get_boost_supply, put_boost_supply, read_balance, read_lock, remove_lock, write_lock},
+    types::{Error, Lock, LockTier},
+    BPS_DENOMINATOR,
+};
+
+const DAY_IN_SECONDS: u64 = 86400;
+
+pub(crate) fn lock_duration(tier: LockTier) -> u64 {
+    match tier {
+        LockTier::Days30 => 30 * DAY_IN_SECONDS,
+        LockTier::Days90 => 90 * DAY_IN_SECONDS,
+        LockTier::Days180 => 180 * DAY_IN_SECONDS,
+    }
+}
+
+/// Weight of the shares locked for `tier` in basis points, unlocked shares weigh `BPS_DENOMINATOR`.
+pub(crate) fn lock_boost(tier: LockTier) -> u32 {
+    match tier {
+        LockTier::Days30 => 11_000,
+        LockTier::Days90 => 12_500,
+        LockTier::Days180 => 15_000,
+    }
+}
+
+/// Shares of `addr` as weighed in the fee distribution.
+pub(crate) fn effective_balance(e: &Env, addr: Address) -> Result<i128, Error> {
+    let extra = if let Some(lock) = read_lock(e, addr.clone()) {
+        lock.extra
+    } else {
+        0
+    };
+
+    read_balance(e, addr).checked_add(extra).ok_or(Error::Overflow)
+}
+
+// Locks merge: the whole position is relocked for the new tier, which can't
+// unlock before the existing lock.
+// The rewards of `addr` must be updated before its lock changes.
+pub(crate) fn lock_shares(e: &Env, addr: Address, shares: i128, tier: LockTier) -> Result<(), Error> {
+    let unlock = e
+        .ledger()
+        .timestamp()
+        .checked_add(lock_duration(tier))
+        .ok_or(Error::Overflow)?;
+
+    let (locked, old_extra) = if let Some(lock) = read_lock(e, addr.clone()) {
+        if unlock < lock.unlock {
+            return Err(Error::InvalidLock);
+        }
+
+        (lock.shares, lock.extra)
+    } else {
+        (0, 0)
+    };
+
+    let boost = lock_boost(tier);
+    let shares = locked.checked_add(shares).ok_or(Error::Overflow)?;
+    let extra = shares
+        .fixed_mul_floor(boost as i128 - BPS_DENOMINATOR, BPS_DENOMINATOR)
+        .ok_or(Error::Overflow)?;
+    let boost_supply = get_boost_supply(e)
+        .checked_sub(old_extra)
+        .and_then(|boost_supply| boost_supply.checked_add(extra))
+        .ok_or(Error::Overflow)?;
+
+    put_boost_supply(e, boost_supply);
+
+    let lock = Lock { shares, tier, unlock, boost, extra };
+    write_lock(e, addr.clone(), &lock);
+    events::locked(e, addr, lock);
+
+    Ok(())
+}
+
+// Expired locks stop boosting once released, anyone can release them through
+// `release_lock()` so that the boost doesn't outlive the lock.
+// The rewards of `addr` must be updated before its lock is released.
+pub(crate) fn release_expired_lock(e: &Env, addr: Address) -> Result<(), Error> {
+    if let Some(lock) = read_lock(e, addr.clone()) {
+        if e.ledger().timestamp() >= lock.unlock {
+            let boost_supply = get_boost_supply(e)
+                .checked_sub(lock.extra)
+                .ok_or(Error::Overflow)?;
+
+            put_boost_supply(e, boost_supply);
+            remove_lock(e, addr.clone());
+            events::lock_released(e, addr, lock.shares);
+        }
+    }
+
+    Ok(())
+}
+
+/// Shares of `addr` that can currently be withdrawn.
+pub(crate) fn unlocked_balance(e: &Env, addr: Address) -> Result<i128, Error> {
+    let locked = if let Some(lock) = read_lock(e, addr.clone()) {
+        if e.ledger().timestamp() < lock.unlock {
+            lock.shares
+        } else {
+            0
+        }
+    } else {
+        0
+    };
+
+    read_balance(e, addr).checked_sub(locked).ok_or(Error::Overflow)
+}
@@ -1,35 +1,171 @@
-use core::ops::{Add, Sub};
-use fixed_point_math::{FixedPoint, STROOP};
+use crate::types::Error;
+#[cfg(test)]
+use fixed_point_math::STROOP;
+use soroban_sdk::{Env, I256};
 
 // Alias to return the dust along with the result
 pub(crate) type I128WithDust = (i128, i128); // result, dust
 
+/// Extra decimals of precision that the fee accumulators carry on top of the token's decimals,
+/// so that tiny fees on huge supplies still increase the fee per share.
+pub const SCALE_EXTRA_DECIMALS: u32 = 12;
+
+/// Largest token decimals for which the accumulators' scale fits an `i128`.
+pub const MAX_DECIMALS: u32 = 24;
+
+// scale of the fee accumulators with 7 decimals of precision, used throughout the tests.
+#[cfg(test)]
+const STROOP_SCALE: i128 = STROOP as i128;
+
+/// Fixed-point scale of the fee accumulators for a token with `decimals` decimals.
+pub fn scale_for_decimals(decimals: u32) -> i128 {
+    10_i128.pow(decimals + SCALE_EXTRA_DECIMALS)
+}
+
+// the intermediate products are computed on 256 bits since they can exceed an `i128` with high scales.
+fn mul_div_floor(env: &Env, x: i128, y: i128, denominator: i128) -> Result<i128, Error> {
+    if denominator <= 0 {
+        return Err(Error::MathError);
+    }
+
+    I256::from_i128(env, x)
+        .mul(&I256::from_i128(env, y))
+        .div(&I256::from_i128(env, denominator))
+        .to_i128()
+        .ok_or(Error::Overflow)
+}
+
+fn mul_div_ceil(env: &Env, x: i128, y: i128, denominator: i128) -> Result<i128, Error> {
+    if denominator <= 0 {
+        return Err(Error::MathError);
+    }
+
+    let denominator = I256::from_i128(env, denominator);
+
+    I256::from_i128(env, x)
+        .mul(&I256::from_i128(env, y))
+        .add(&denominator)
+        .sub(&I256::from_i128(env, 1))
+        .div(&denominator)
+        .to_i128()
+        .ok_or(Error::Overflow)
+}
+
 pub fn compute_fee_per_share(
+    env: &Env,
     fee_per_share_universal: i128,
     accrued_interest: i128,
     total_supply: i128,
-) -> I128WithDust {
-    let interest_by_supply = accrued_interest.fixed_div_floor(total_supply, STROOP.into()).unwrap();
-    let computed_floored = fee_per_share_universal.add(interest_by_supply);
-    let dust = accrued_interest - interest_by_supply.fixed_mul_ceil(total_supply, STROOP.into()).unwrap();
+    scale: i128,
+) -> Result<I128WithDust, Error> {
+    let interest_by_supply = mul_div_floor(env, accrued_interest, scale, total_supply)?;
+    let computed_floored = fee_per_share_universal
+        .checked_add(interest_by_supply)
+        .ok_or(Error::Overflow)?;
+    let dust = accrued_interest
+        .checked_sub(mul_div_ceil(env, interest_by_supply, total_supply, scale)?)
+        .ok_or(Error::Overflow)?;
 
-    (computed_floored, dust)
+    Ok((computed_floored, dust))
 }
 
 pub fn compute_fee_earned(
+    env: &Env,
     user_balance: i128,
     fee_per_share_universal: i128,
     fee_per_share_particular: i128,
-) -> i128 {
-    user_balance
-        .fixed_mul_floor(
-            fee_per_share_universal.sub(fee_per_share_particular),
-            STROOP.into(),
-        )
-        .unwrap()
+    scale: i128,
+) -> Result<i128, Error> {
+    mul_div_floor(
+        env,
+        user_balance,
+        fee_per_share_universal
+            .checked_sub(fee_per_share_particular)
+            .ok_or(Error::Overflow)?,
+        scale,
+    )
 }
 
 #[test]
 fn test_dust() {
-    compute_fee_per_share(3 * STROOP as i128, 50 * STROOP as i128, 200001 * STROOP as i128);
+    let env = Env::default();
+    compute_fee_per_share(&env, 3 * STROOP as i128, 50 * STROOP as i128, 200001 * STROOP as i128, STROOP_SCALE).unwrap();
+}
+
+#[test]
+fn test_stroop_scale() {
+    let env = Env::default();
+
+    // a 0.08% fee on a loan of the whole supply.
+    let supply = 100 * STROOP as i128;
+    let (fee_per_share, dust) = compute_fee_per_share(&env, 0, 800_000, supply, STROOP_SCALE).unwrap();
+    assert_eq!(fee_per_share, 8_000);
+    assert_eq!(dust, 0);
+    assert_eq!(compute_fee_earned(&env, supply / 4, fee_per_share, 0, STROOP_SCALE).unwrap(), 200_000);
+}
+
+#[test]
+fn test_decimal_scales() {
+    let env = Env::default();
+
+    for decimals in [0_u32, 2, 6, 7, 9, 18, MAX_DECIMALS] {
+        let unit = 10_i128.pow(decimals);
+        let scale = scale_for_decimals(decimals);
+
+        // a huge supply of a billion tokens only earning the smallest fee possible.
+        let supply = 1_000_000_000 * unit;
+        let (fee_per_share, dust) = compute_fee_per_share(&env, 0, 1, supply, scale).unwrap();
+        assert!(fee_per_share > 0, "{} decimals", decimals);
+        assert_eq!(dust, 0, "{} decimals", decimals);
+
+        // nothing is lost nor created when splitting the fee.
+        let earned = compute_fee_earned(&env, supply, fee_per_share, 0, scale).unwrap();
+        assert_eq!(earned, 1, "{} decimals", decimals);
+
+        // a 0.08% fee on a loan of the whole supply is split 1:3 exactly.
+        let fee = supply * 8 / 10_000;
+        let (fee_per_share, dust) = compute_fee_per_share(&env, 0, fee, supply, scale).unwrap();
+        assert_eq!(dust, 0, "{} decimals", decimals);
+        assert_eq!(compute_fee_earned(&env, supply / 4, fee_per_share, 0, scale).unwrap(), fee / 4, "{} decimals", decimals);
+        assert_eq!(compute_fee_earned(&env, supply - supply / 4, fee_per_share, 0, scale).unwrap(), fee - fee / 4, "{} decimals", decimals);
+    }
+}
+
+#[test]
+fn test_stroop_scale_loses_precision() {
+    let env = Env::default();
+
+    // with the legacy scale the smallest fee on a supply of 18 decimals tokens stays dust.
+    let supply = 1_000_000_000 * 10_i128.pow(18);
+    let (fee_per_share, dust) = compute_fee_per_share(&env, 0, 1, supply, STROOP_SCALE).unwrap();
+    assert_eq!(fee_per_share, 0);
+    assert_eq!(dust, 1);
+}
+
+#[test]
+fn test_overflow() {
+    let env = Env::default();
+    let scale = scale_for_decimals(7);
+
+    // the fee earned by a huge balance since a huge accumulator increase doesn't fit an `i128`.
+    assert_eq!(compute_fee_earned(&env, i128::MAX, i128::MAX, 0, STROOP_SCALE), Err(Error::Overflow));
+    assert_eq!(compute_fee_earned(&env, 1, i128::MIN, 1, scale), Err(Error::Overflow));
+
+    // the universal accumulator can't grow past `i128::MAX`.
+    assert_eq!(compute_fee_per_share(&env, i128::MAX, 1, 1, scale), Err(Error::Overflow));
+    assert_eq!(compute_fee_per_share(&env, 0, i128::MAX, 1, scale), Err(Error::Overflow));
+
+    // right below the limit the accumulator still works.
+    let (fee_per_share, dust) = compute_fee_per_share(&env, i128::MAX - 1, 1, 1, 1).unwrap();
+    assert_eq!(fee_per_share, i128::MAX);
+    assert_eq!(dust, 0);
+}
+
+#[test]
+fn test_math_error() {
+    let env = Env::default();
+
+    // fees can't be distributed over an empty supply nor with a degenerate scale.
+    assert_eq!(compute_fee_per_share(&env, 0, 1, 0, STROOP_SCALE), Err(Error::MathError));
+    assert_eq!(compute_fee_earned(&env, 1, 1, 0, 0), Err(Error::MathError));
 }
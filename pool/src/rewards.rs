@@ -1,42 +1,62 @@
 use crate::{
-    events, math::{compute_fee_earned, compute_fee_per_share, I128WithDust}, storage::*, token_utility::{get_token_client, transfer}, types::Error
+    events, incentives::update_incentives, insolvency::apply_haircut, lock::{effective_balance, release_expired_lock}, math::{compute_fee_earned, compute_fee_per_share, I128WithDust}, storage::*, token_utility::{get_token_client, transfer}, types::Error
 };
-use core::ops::AddAssign;
 use soroban_sdk::{Address, Env};
 
-pub(crate) fn update_rewards(e: &Env, addr: Address) {
+pub(crate) fn update_rewards(e: &Env, addr: Address) -> Result<(), Error> {
     let fee_per_share_universal = get_fee_per_share_universal(e);
+    let balance = effective_balance(e, addr.clone())?;
     let lender_fees = compute_fee_earned(
-        read_balance(e, addr.clone()),
+        e,
+        balance,
         fee_per_share_universal,
         read_fee_per_share_particular(e, addr.clone()),
-    );
+        get_scale(e),
+    )?;
 
     write_fee_per_share_particular(e, addr.clone(), fee_per_share_universal);
     
-    let mut matured = read_matured_fees_particular(e, addr.clone());
-    matured.add_assign(lender_fees);
+    let matured = read_matured_fees_particular(e, addr.clone())
+        .checked_add(lender_fees)
+        .ok_or(Error::Overflow)?;
     
     write_matured_fees_particular(e, addr.clone(), matured);
+
+    // incentives are checkpointed before the expired lock stops boosting the balance.
+    update_incentives(e, addr.clone(), balance);
+    release_expired_lock(e, addr.clone())?;
     events::new_fees(e, addr, lender_fees);
+
+    Ok(())
 }
 
-pub(crate) fn update_fee_per_share_universal(e: &Env, collected: i128) {
+pub(crate) fn update_fee_per_share_universal(e: &Env, collected: i128) -> Result<(), Error> {
     let fee_per_share_universal = get_fee_per_share_universal(e);
-    let total_supply = get_tot_supply(e);
+    // locked shares weigh more than their raw amount
+    let total_supply = get_tot_supply(e)
+        .checked_add(get_boost_supply(e))
+        .ok_or(Error::Overflow)?;
+    let distributed = collected
+        .checked_add(read_dust(e))
+        .ok_or(Error::Overflow)?;
     
     // computing the new universal fee per share in light of the collected interest
     let (adjusted_fee_per_share_universal, dust): I128WithDust =
-        compute_fee_per_share(fee_per_share_universal, collected + read_dust(e), total_supply);
+        compute_fee_per_share(e, fee_per_share_universal, distributed, total_supply, get_scale(e))?;
+    let liabilities = get_liabilities(e)
+        .checked_add(collected)
+        .ok_or(Error::Overflow)?;
 
-    if dust > 0 {
-        write_dust(e, dust);
-    }
+    // the dust is carried over to the next distribution, including when it was all distributed.
+    write_dust(e, dust);
 
     put_fee_per_share_universal(e, adjusted_fee_per_share_universal);
+    put_liabilities(e, liabilities);
+
+    Ok(())
 }
 
-pub(crate) fn pay_matured(e: &Env, addr: Address) -> Result<i128, Error> {
+pub(crate) fn pay_matured(e: &Env, addr: Address, to: &Address) -> Result<i128, Error> {
     let token_client = get_token_client(e);
 
     // collect all the fees matured by the lender `addr`
@@ -46,9 +66,15 @@ pub(crate) fn pay_matured(e: &Env, addr: Address) -> Result<i128, Error> {
         return Err(Error::NoFeesMatured);
     }
 
-    // transfer the matured yield to `addr` and update the particular matured fees storage slot
-    transfer(e, &token_client, &addr, &matured);
+    // transfer the matured yield of `addr` to `to` and update the particular matured fees storage slot
+    let paid = apply_haircut(e, &token_client, matured)?;
+    let liabilities = get_liabilities(e)
+        .checked_sub(matured)
+        .ok_or(Error::Overflow)?;
+
+    transfer(e, &token_client, to, &paid);
     write_matured_fees_particular(e, addr, 0);
+    put_liabilities(e, liabilities);
 
-    Ok(matured)
+    Ok(paid)
 }
@@ -1,7 +1,7 @@
-use soroban_sdk::{Address, Env};
+use soroban_sdk::{unwrap::UnwrapOptimized, Address, Env, Vec};
 
 use crate::{
-    types::{DataKey, Error}, INSTANCE_LEDGER_LIFE, INSTANCE_LEDGER_TTL_THRESHOLD, PERSISTENT_LEDGER_LIFE, PERSISTENT_LEDGER_TTL_THRESHOLD
+    types::{ActiveLoan, DataKey, Error, Incentive, LoanLimits, Lock, MaxLoan, PoolStatus}, INSTANCE_LEDGER_LIFE, INSTANCE_LEDGER_TTL_THRESHOLD, PERSISTENT_LEDGER_LIFE, PERSISTENT_LEDGER_TTL_THRESHOLD
 };
 
 // User specific state.
@@ -29,6 +29,62 @@ pub(crate) fn read_balance(e: &Env, addr: Address) -> i128 {
     }
 }
 
+pub(crate) fn write_lock(e: &Env, addr: Address, lock: &Lock) {
+    let key = DataKey::Lock(addr);
+    e.storage().persistent().set(&key, lock);
+    bump_persistent(e, &key);
+}
+
+pub(crate) fn read_lock(e: &Env, addr: Address) -> Option<Lock> {
+    let key = DataKey::Lock(addr);
+    let lock = e.storage().persistent().get(&key);
+
+    if lock.is_some() {
+        bump_persistent(e, &key);
+    }
+
+    lock
+}
+
+pub(crate) fn remove_lock(e: &Env, addr: Address) {
+    let key = DataKey::Lock(addr);
+    e.storage().persistent().remove(&key);
+}
+
+pub(crate) fn write_incentive_per_share_paid(e: &Env, addr: Address, reward_token: Address, amount: i128) {
+    let key = DataKey::IncentivePerSharePaid(addr, reward_token);
+    e.storage().persistent().set(&key, &amount);
+    bump_persistent(e, &key);
+}
+
+pub(crate) fn read_incentive_per_share_paid(e: &Env, addr: Address, reward_token: Address) -> i128 {
+    let key = DataKey::IncentivePerSharePaid(addr, reward_token);
+
+    if let Some(paid) = e.storage().persistent().get(&key) {
+        bump_persistent(e, &key);
+        paid
+    } else {
+        0
+    }
+}
+
+pub(crate) fn write_incentives_accrued(e: &Env, addr: Address, reward_token: Address, amount: i128) {
+    let key = DataKey::IncentivesAccrued(addr, reward_token);
+    e.storage().persistent().set(&key, &amount);
+    bump_persistent(e, &key);
+}
+
+pub(crate) fn read_incentives_accrued(e: &Env, addr: Address, reward_token: Address) -> i128 {
+    let key = DataKey::IncentivesAccrued(addr, reward_token);
+
+    if let Some(accrued) = e.storage().persistent().get(&key) {
+        bump_persistent(e, &key);
+        accrued
+    } else {
+        0
+    }
+}
+
 pub(crate) fn write_fee_per_share_particular(e: &Env, addr: Address, amount: i128) {
     let key = DataKey::FeePerShareParticular(addr);
     e.storage().persistent().set(&key, &amount);
@@ -83,6 +139,105 @@ pub(crate) fn get_tot_supply(e: &Env) -> i128 {
     e.storage().instance().get(&key).unwrap_or(0)
 }
 
+pub(crate) fn put_boost_supply(e: &Env, supply: i128) {
+    let key = DataKey::BoostSupply;
+    e.storage().instance().set(&key, &supply);
+}
+
+// shares that locked deposits weigh on top of `TotSupply` in the fee distribution.
+pub(crate) fn get_boost_supply(e: &Env) -> i128 {
+    let key = DataKey::BoostSupply;
+    e.storage().instance().get(&key).unwrap_or(0)
+}
+
+pub(crate) fn put_reward_tokens(e: &Env, reward_tokens: &Vec<Address>) {
+    let key = DataKey::RewardTokens;
+    e.storage().instance().set(&key, reward_tokens);
+}
+
+pub(crate) fn get_reward_tokens(e: &Env) -> Vec<Address> {
+    let key = DataKey::RewardTokens;
+    e.storage().instance().get(&key).unwrap_or(Vec::new(e))
+}
+
+pub(crate) fn write_incentive(e: &Env, reward_token: Address, incentive: &Incentive) {
+    let key = DataKey::Incentive(reward_token);
+    e.storage().persistent().set(&key, incentive);
+    bump_persistent(e, &key);
+}
+
+pub(crate) fn read_incentive(e: &Env, reward_token: Address) -> Option<Incentive> {
+    let key = DataKey::Incentive(reward_token);
+    let incentive = e.storage().persistent().get(&key);
+
+    if incentive.is_some() {
+        bump_persistent(e, &key);
+    }
+
+    incentive
+}
+
+pub(crate) fn write_distributor(e: &Env, distributor: Address, approved: bool) {
+    let key = DataKey::Distributor(distributor);
+    if approved {
+        e.storage().persistent().set(&key, &true);
+        bump_persistent(e, &key);
+    } else {
+        e.storage().persistent().remove(&key);
+    }
+}
+
+pub(crate) fn is_distributor(e: &Env, distributor: Address) -> bool {
+    let key = DataKey::Distributor(distributor);
+    e.storage().persistent().has(&key)
+}
+
+pub(crate) fn put_liabilities(e: &Env, liabilities: i128) {
+    let key = DataKey::Liabilities;
+    e.storage().instance().set(&key, &liabilities);
+}
+
+// fees collected by the pool which are yet to be paid out to the liquidity providers,
+// whether they're matured, still to be checkpointed, or dust.
+pub(crate) fn get_liabilities(e: &Env) -> i128 {
+    let key = DataKey::Liabilities;
+    e.storage().instance().get(&key).unwrap_or(0)
+}
+
+pub(crate) fn put_insolvent(e: &Env, insolvent: bool) {
+    let key = DataKey::Insolvent;
+    e.storage().instance().set(&key, &insolvent);
+}
+
+pub(crate) fn is_insolvent(e: &Env) -> bool {
+    let key = DataKey::Insolvent;
+    e.storage().instance().get(&key).unwrap_or(false)
+}
+
+pub(crate) fn put_loan_limits(e: &Env, limits: &LoanLimits) {
+    let key = DataKey::LoanLimits;
+    e.storage().instance().set(&key, limits);
+}
+
+pub(crate) fn get_loan_limits(e: &Env) -> LoanLimits {
+    let key = DataKey::LoanLimits;
+    e.storage().instance().get(&key).unwrap_or(LoanLimits {
+        min_loan: 0,
+        max_loan: MaxLoan::Unlimited,
+        max_volume_per_ledger: 0,
+    })
+}
+
+pub(crate) fn put_scale(e: &Env, scale: i128) {
+    let key = DataKey::Scale;
+    e.storage().instance().set(&key, &scale);
+}
+
+pub(crate) fn get_scale(e: &Env) -> i128 {
+    let key = DataKey::Scale;
+    e.storage().instance().get(&key).unwrap_optimized()
+}
+
 pub(crate) fn put_fee_per_share_universal(e: &Env, last_recorded: i128) {
     let key = DataKey::FeePerShareUniversal;
     e.storage().instance().set(&key, &last_recorded);
@@ -93,11 +248,6 @@ pub(crate) fn get_fee_per_share_universal(e: &Env) -> i128 {
     e.storage().instance().get(&key).unwrap_or(0)
 }
 
-pub(crate) fn has_token_id(e: &Env) -> bool {
-    let key = DataKey::TokenId;
-    e.storage().instance().has(&key)
-}
-
 pub(crate) fn put_token_id(e: &Env, token_id: Address) {
     let key = DataKey::TokenId;
     e.storage().instance().set(&key, &token_id);
@@ -113,6 +263,51 @@ pub(crate) fn get_token_id(e: &Env) -> Result<Address, Error> {
     }
 }
 
+pub(crate) fn put_factory(e: &Env, factory: Address) {
+    let key = DataKey::Factory;
+    e.storage().instance().set(&key, &factory);
+}
+
+pub(crate) fn get_factory(e: &Env) -> Result<Address, Error> {
+    let key = DataKey::Factory;
+
+    if let Some(factory) = e.storage().instance().get(&key) {
+        Ok(factory)
+    } else {
+        Err(Error::NotManaged)
+    }
+}
+
+pub(crate) fn put_fee_rate(e: &Env, fee_rate: u32) {
+    let key = DataKey::FeeRate;
+    e.storage().instance().set(&key, &fee_rate);
+}
+
+pub(crate) fn get_fee_rate(e: &Env) -> u32 {
+    let key = DataKey::FeeRate;
+    e.storage().instance().get(&key).unwrap_optimized()
+}
+
+pub(crate) fn put_status(e: &Env, status: PoolStatus) {
+    let key = DataKey::Status;
+    e.storage().instance().set(&key, &status);
+}
+
+pub(crate) fn get_status(e: &Env) -> PoolStatus {
+    let key = DataKey::Status;
+    e.storage().instance().get(&key).unwrap_or(PoolStatus::Active)
+}
+
+pub(crate) fn put_paused(e: &Env, paused: bool) {
+    let key = DataKey::Paused;
+    e.storage().instance().set(&key, &paused);
+}
+
+pub(crate) fn is_paused(e: &Env) -> bool {
+    let key = DataKey::Paused;
+    e.storage().instance().get(&key).unwrap_or(false)
+}
+
 pub(crate) fn write_dust(e: &Env, dust: i128) {
     let key = DataKey::Dust;
     e.storage().instance().set(&key, &dust);
@@ -123,6 +318,59 @@ pub(crate) fn read_dust(e: &Env) -> i128 {
     e.storage().instance().get(&key).unwrap_or(0)
 }
 
+// TEMPORARY
+
+// an active loan only lives within the transaction that lent it out.
+
+pub(crate) fn write_active_loan(e: &Env, loan: &ActiveLoan) {
+    let key = DataKey::ActiveLoan;
+    e.storage().temporary().set(&key, loan);
+}
+
+pub(crate) fn read_active_loan(e: &Env) -> Option<ActiveLoan> {
+    let key = DataKey::ActiveLoan;
+    e.storage().temporary().get(&key)
+}
+
+pub(crate) fn has_active_loan(e: &Env) -> bool {
+    let key = DataKey::ActiveLoan;
+    e.storage().temporary().has(&key)
+}
+
+pub(crate) fn remove_active_loan(e: &Env) {
+    let key = DataKey::ActiveLoan;
+    e.storage().temporary().remove(&key)
+}
+
+// the volume lent out within a ledger is keyed by the ledger, so that it resets on the next one.
+
+pub(crate) fn write_volume(e: &Env, volume: i128) {
+    let key = DataKey::Volume(e.ledger().sequence());
+    e.storage().temporary().set(&key, &volume);
+}
+
+pub(crate) fn read_volume(e: &Env) -> i128 {
+    let key = DataKey::Volume(e.ledger().sequence());
+    e.storage().temporary().get(&key).unwrap_or(0)
+}
+
+// a direct loan is flagged while the receiver executes, so that the pool can't lend again meanwhile.
+
+pub(crate) fn write_borrowing(e: &Env) {
+    let key = DataKey::Borrowing;
+    e.storage().temporary().set(&key, &true);
+}
+
+pub(crate) fn is_borrowing(e: &Env) -> bool {
+    let key = DataKey::Borrowing;
+    e.storage().temporary().has(&key)
+}
+
+pub(crate) fn remove_borrowing(e: &Env) {
+    let key = DataKey::Borrowing;
+    e.storage().temporary().remove(&key)
+}
+
 // shouldn't be needed because of state expiration
 
 pub(crate) fn _remove_matured_fees_particular(e: &Env, addr: Address) {
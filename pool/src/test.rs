@@ -1,6 +1,9 @@
 use fixed_point_math::{FixedPoint, STROOP};
 
 use crate::contract::{Pool, PoolClient};
+use crate::rewards::update_fee_per_share_universal;
+use crate::storage::{get_fee_per_share_universal, get_liabilities, get_scale, put_fee_per_share_universal, put_liabilities, put_tot_supply, read_dust, write_borrowing};
+use crate::types::Error;
 
 use soroban_sdk::{
     contract, contractimpl, symbol_short, testutils::Address as _, token, Address, Env, Symbol,
@@ -23,7 +26,7 @@ fn collect_yield_raw() {
     let token_admin = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let pool_addr = env.register_contract(&None, Pool);
+    let pool_addr = env.register(Pool, (token_id.clone(), 8_u32, None::<Address>));
     let pool_client = PoolClient::new(&env, &pool_addr);
 
     let receiver = env.register_contract(None, FlashLoanReceiver);
@@ -31,7 +34,6 @@ fn collect_yield_raw() {
 
     // Initialize the flash loan receiver contract.
     receiver_client.init(&user1, &token_id, &pool_addr);
-    pool_client.initialize(&token_id);
 
     token_admin.mint(&receiver, &(1000 * STROOP as i128));
     token_admin.mint(&user1, &(100 * STROOP as i128));
@@ -51,6 +53,182 @@ fn collect_yield_raw() {
     assert_eq!(token.balance(&user1), expected_yield);
 }
 
+// Tests that the fees owed to the liquidity providers are tracked as
+// liabilities, and that a pool which can't pay them stops lending.
+#[test]
+fn liabilities() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin1 = Address::generate(&env);
+
+    let user1 = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(admin1);
+    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+
+    let pool_addr = env.register(Pool, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = PoolClient::new(&env, &pool_addr);
+
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    let receiver_client = FlashLoanReceiverClient::new(&env, &receiver);
+    receiver_client.init(&user1, &token_id, &pool_addr);
+
+    token_admin.mint(&receiver, &(1000 * STROOP as i128));
+    token_admin.mint(&user1, &(100 * STROOP as i128));
+
+    pool_client.deposit(&user1, &(100 * STROOP as i128));
+
+    pool_client.borrow(&receiver, &(100 * STROOP as i128));
+    assert_eq!(pool_client.liabilities(), 800_000);
+
+    // maturing fees doesn't change what the pool owes.
+    pool_client.update_fee_rewards(&user1);
+    assert_eq!(pool_client.liabilities(), 800_000);
+
+    pool_client.withdraw_matured(&user1);
+    assert_eq!(pool_client.liabilities(), 0);
+
+    // the pool now owes more than it holds.
+    env.as_contract(&pool_addr, || put_liabilities(&env, 1));
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &(100 * STROOP as i128)),
+        Err(Ok(Error::BalanceLtSupply))
+    );
+}
+
+// Tests that the pool's accounting returns an error instead of
+// wrapping or panicking when values get close to `i128::MAX`.
+#[test]
+fn overflow() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin1 = Address::generate(&env);
+
+    let user1 = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(admin1);
+    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+
+    let pool_addr = env.register(Pool, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = PoolClient::new(&env, &pool_addr);
+
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    let receiver_client = FlashLoanReceiverClient::new(&env, &receiver);
+    receiver_client.init(&user1, &token_id, &pool_addr);
+
+    token_admin.mint(&receiver, &(1000 * STROOP as i128));
+    token_admin.mint(&user1, &(101 * STROOP as i128));
+
+    pool_client.deposit(&user1, &(100 * STROOP as i128));
+
+    // the fee on a huge loan doesn't fit, the loan is rejected before any funds move.
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &i128::MAX),
+        Err(Ok(Error::Overflow))
+    );
+
+    // the supply can't grow past `i128::MAX`.
+    env.as_contract(&pool_addr, || put_tot_supply(&env, i128::MAX));
+    assert_eq!(
+        pool_client.try_deposit(&user1, &1),
+        Err(Ok(Error::Overflow))
+    );
+    env.as_contract(&pool_addr, || put_tot_supply(&env, 100 * STROOP as i128));
+
+    // nor can the universal fee per share.
+    env.as_contract(&pool_addr, || put_fee_per_share_universal(&env, i128::MAX));
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &(100 * STROOP as i128)),
+        Err(Ok(Error::Overflow))
+    );
+    env.as_contract(&pool_addr, || put_fee_per_share_universal(&env, 0));
+
+    // the pool works as usual once back in range.
+    pool_client.borrow(&receiver, &(100 * STROOP as i128));
+    assert_eq!(pool_client.liabilities(), 800_000);
+}
+
+// Tests that dust which was fully distributed isn't carried over again,
+// so that the fees owed to the liquidity providers never exceed the liabilities.
+#[test]
+fn dust_carry_over() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+    let pool_addr = env.register(Pool, (token_id.clone(), 8_u32, None::<Address>));
+
+    env.as_contract(&pool_addr, || {
+        // a supply larger than the scale leaves whole units of dust.
+        let scale = get_scale(&env);
+        let supply = 3 * scale;
+        put_tot_supply(&env, supply);
+        let owed = || get_fee_per_share_universal(&env) * supply / scale;
+
+        update_fee_per_share_universal(&env, 1).unwrap();
+        assert_eq!(read_dust(&env), 1);
+        assert_eq!(get_fee_per_share_universal(&env), 0);
+
+        // 2 + 1 of dust are distributed evenly, nothing is left.
+        update_fee_per_share_universal(&env, 2).unwrap();
+        assert_eq!(read_dust(&env), 0);
+        assert_eq!(get_fee_per_share_universal(&env), 1);
+        assert_eq!(owed(), get_liabilities(&env));
+
+        update_fee_per_share_universal(&env, 2).unwrap();
+        assert_eq!(read_dust(&env), 2);
+        assert_eq!(get_fee_per_share_universal(&env), 1);
+        assert_eq!(owed() + read_dust(&env), get_liabilities(&env));
+    });
+}
+
+// Tests that while one of its loans is out the pool neither lends again
+// nor lets liquidity move. The host already rejects a receiver re-entering
+// the pool, the flag is set directly to exercise the pool's own guard.
+#[test]
+fn nested_loan() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin1 = Address::generate(&env);
+
+    let user1 = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(admin1);
+    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+
+    let pool_addr = env.register(Pool, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = PoolClient::new(&env, &pool_addr);
+
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    let receiver_client = FlashLoanReceiverClient::new(&env, &receiver);
+    receiver_client.init(&user1, &token_id, &pool_addr);
+
+    token_admin.mint(&receiver, &(1000 * STROOP as i128));
+    token_admin.mint(&user1, &(200 * STROOP as i128));
+
+    pool_client.deposit(&user1, &(100 * STROOP as i128));
+
+    env.as_contract(&pool_addr, || write_borrowing(&env));
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &(100 * STROOP as i128)),
+        Err(Ok(Error::NestedLoan))
+    );
+    assert_eq!(
+        pool_client.try_deposit(&user1, &(100 * STROOP as i128)),
+        Err(Ok(Error::LoanInProgress))
+    );
+    assert_eq!(
+        pool_client.try_withdraw(&user1, &(100 * STROOP as i128)),
+        Err(Ok(Error::LoanInProgress))
+    );
+}
+
 #[contract]
 pub struct FlashLoanReceiver;
 
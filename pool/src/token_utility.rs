@@ -19,16 +19,38 @@ pub(crate) fn get_token_client(e: &Env) -> token::Client {
     )
 }
 
-pub(crate) fn transfer_in_pool(env: &Env, client: &token::Client, from: &Address, amount: &i128) {
+// The pool's own balance is measured around the transfer, so that tokens which tax
+// transfers or rebase are accounted for by what the pool actually received.
+fn pool_balance(env: &Env, client: &token::Client) -> i128 {
+    client.balance(&env.current_contract_address())
+}
+
+/// Moves `amount` from `from` into the pool, returns what the pool actually received.
+pub(crate) fn transfer_in_pool(env: &Env, client: &token::Client, from: &Address, amount: &i128) -> Result<i128, Error> {
+    let before = pool_balance(env, client);
     client.transfer(from, &env.current_contract_address(), amount);
+
+    let received = pool_balance(env, client)
+        .checked_sub(before)
+        .ok_or(Error::Overflow)?;
+
+    // nothing to credit when the whole transfer was taxed away.
+    if received <= 0 {
+        return Err(Error::InvalidAmount);
+    }
+
+    Ok(received)
 }
 
+/// Pulls `amount` from `from` with the allowance given to the pool, returns what the pool actually received.
 pub(crate) fn transfer_from_to_pool(
     e: &Env,
     client: &token::Client,
     from: &Address,
     amount: &i128,
-) -> Result<(), Error> {
+) -> Result<i128, Error> {
+    let before = pool_balance(e, client);
+
     // catch the result of the `xfer_from` operation
     let res = client.try_transfer_from(
         &e.current_contract_address(),
@@ -38,12 +60,22 @@ pub(crate) fn transfer_from_to_pool(
     );
 
     // if the xfer failed, then the receiver contract hasn't paid back the debt + fees
-    if let Ok(Ok(_)) = res {
-        Ok(())
-    } else {
-        Err(Error::LoanNotRepaid)
+    if !matches!(res, Ok(Ok(_))) {
+        return Err(Error::LoanNotRepaid);
+    }
+
+    let received = pool_balance(e, client)
+        .checked_sub(before)
+        .ok_or(Error::Overflow)?;
+
+    // a transfer that arrives short of `amount` doesn't repay the debt
+    if received < *amount {
+        return Err(Error::LoanNotRepaid);
     }
+
+    Ok(received)
 }
+
 pub(crate) fn try_repay(
     e: &Env,
     client: &token::Client,
@@ -52,12 +84,13 @@ pub(crate) fn try_repay(
     fee: i128,
 ) -> Result<(), Error> {
     // xfer back the lent capital + fees from the receiver contract to the flash loan
-    transfer_from_to_pool(e, client, receiver_id, &(amount + fee))?;
+    let debt = amount.checked_add(fee).ok_or(Error::Overflow)?;
+    let received = transfer_from_to_pool(e, client, receiver_id, &debt)?;
 
     // loan is now repaid with interest.
     // we need to update the fee_per_share_universal
-    // parameter since we inputted more money in the pool.
-    update_fee_per_share_universal(&e, fee);
-
-    Ok(())
+    // parameter since we inputted more money in the pool,
+    // everything received on top of the principal is distributed.
+    let collected = received.checked_sub(amount).ok_or(Error::Overflow)?;
+    update_fee_per_share_universal(&e, collected)
 }
@@ -7,20 +7,142 @@ pub enum DataKey {
     TotSupply,
     FeePerShareUniversal,
     Dust,
+    Factory,
+    FeeRate,
+    Status,
+    Paused,
+    BoostSupply,
+    RewardTokens,
+    Liabilities,
+    Insolvent,
+    Scale,
+    LoanLimits,
+    ActiveLoan,
+    Borrowing,
+    Volume(u32),
     Balance(Address),
     FeePerShareParticular(Address),
     MaturedFeesParticular(Address),
+    Lock(Address),
+    Distributor(Address),
+    Incentive(Address),
+    IncentivePerSharePaid(Address, Address),
+    IncentivesAccrued(Address, Address),
+}
+
+/// Lifecycle of the pool, managed by the factory.
+/// Deprecated pools stop accepting deposits, sunset pools also stop lending.
+/// Withdrawals are always open.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum PoolStatus {
+    Active,
+    Deprecated,
+    Sunset,
+}
+
+/// Loan lent out through the factory which is yet to be settled.
+#[derive(Clone)]
+#[contracttype]
+pub struct ActiveLoan {
+    pub receiver: Address,
+    pub amount: i128,
+    pub fee: i128,
+    /// Pool balance before the principal was lent out.
+    pub balance: i128,
+}
+
+/// Commitment periods for locked deposits, longer locks earn a larger share of the fees.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum LockTier {
+    Days30,
+    Days90,
+    Days180,
+}
+
+/// Shares of a liquidity provider that can't be withdrawn before `unlock`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Lock {
+    pub shares: i128,
+    pub tier: LockTier,
+    /// Ledger timestamp from which the shares can be withdrawn.
+    pub unlock: u64,
+    /// Weight of the locked shares in the fee distribution, in basis points.
+    pub boost: u32,
+    /// Shares accounted on top of `shares` in the fee distribution.
+    pub extra: i128,
+}
+
+/// Incentive program paying a reward token to the liquidity providers of the pool.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Incentive {
+    /// Amount of the reward token distributed every ledger.
+    pub rate: i128,
+    /// Ledger at which the distribution stops.
+    pub end_ledger: u32,
+    /// Ledger up to which `reward_per_share` is accrued.
+    pub last_ledger: u32,
+    /// Reward token distributed per effective share since the first program.
+    pub reward_per_share: i128,
+}
+
+/// Largest loan the pool lends.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum MaxLoan {
+    Unlimited,
+    /// Amount of the pool's token.
+    Amount(i128),
+    /// Share of the pool's liquidity, in basis points.
+    Share(u32),
+}
+
+/// Limits on the loans of the pool, set by the factory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LoanLimits {
+    /// Smallest loan the pool lends, 0 for no limit.
+    pub min_loan: i128,
+    pub max_loan: MaxLoan,
+    /// Largest amount lent out within a ledger, 0 for no limit.
+    pub max_volume_per_ledger: i128,
 }
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum Error {
-    AlreadyInitialized = 0,
     NotInitialized = 1,
     InvalidShareBalance = 2,
     NoFeesMatured = 3,
     LoanNotRepaid = 4,
     BalanceLtSupply = 5,
-    InvalidAmount = 6
+    InvalidAmount = 6,
+    NotManaged = 7,
+    LoanInProgress = 8,
+    NoActiveLoan = 9,
+    InvalidFeeRate = 10,
+    PoolDeprecated = 11,
+    PoolSunset = 12,
+    NoLiquidity = 13,
+    Paused = 14,
+    SharesLocked = 15,
+    InvalidLock = 16,
+    NotDistributor = 17,
+    InvalidRewardToken = 18,
+    IncentiveActive = 19,
+    InvalidIncentive = 20,
+    TooManyRewardTokens = 21,
+    Insolvent = 22,
+    UnsupportedDecimals = 23,
+    Overflow = 24,
+    MathError = 25,
+    NestedLoan = 26,
+    LoanTooSmall = 27,
+    LoanTooLarge = 28,
+    VolumeExceeded = 29,
+    InvalidLoanLimits = 30
 }
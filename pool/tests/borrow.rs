@@ -0,0 +1,30 @@
+mod pool {
+    use soroban_sdk::contractimport;
+
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+use soroban_sdk::{testutils::Address as _, token, Address, Env};
+
+// Tests that borrowing from a pool without liquidity fails cleanly
+// even when the pool holds some tokens.
+#[should_panic(expected = "HostError: Error(Contract, #13)")]
+#[test]
+fn borrow_no_liquidity() {
+    let e: Env = Default::default();
+    e.mock_all_auths();
+
+    let admin1 = Address::generate(&e);
+    let receiver = Address::generate(&e);
+
+    let token_id = e.register_stellar_asset_contract(admin1);
+    let token_admin = token::StellarAssetClient::new(&e, &token_id);
+
+    let pool_addr = e.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = pool::Client::new(&e, &pool_addr);
+
+    // tokens sent directly to the pool don't mint any shares.
+    token_admin.mint(&pool_addr, &1000000000);
+
+    pool_client.borrow(&receiver, &1000000000);
+}
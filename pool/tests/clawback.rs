@@ -0,0 +1,152 @@
+mod pool {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+use fixed_point_math::STROOP;
+use soroban_sdk::{
+    contract, contractimpl, symbol_short,
+    testutils::{Address as _, IssuerFlags},
+    token, Address, Env, Error, Symbol,
+};
+
+// Tests that once part of the pool's balance is clawed back, the pool
+// enters insolvency mode and every liquidity provider takes the same haircut
+// on both its deposit and its matured fees.
+#[test]
+fn clawback_haircut() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin1 = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+
+    let sac = env.register_stellar_asset_contract_v2(admin1);
+    sac.issuer().set_flag(IssuerFlags::RevocableFlag);
+    sac.issuer().set_flag(IssuerFlags::ClawbackEnabledFlag);
+
+    let token_id = sac.address();
+    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+    let token = token::Client::new(&env, &token_id);
+
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    FlashLoanReceiverClient::new(&env, &receiver).init(&token_id, &pool_addr);
+    token_admin.mint(&receiver, &(10 * STROOP as i128));
+
+    token_admin.mint(&user1, &(100 * STROOP as i128));
+    token_admin.mint(&user2, &(100 * STROOP as i128));
+    pool_client.deposit(&user1, &(100 * STROOP as i128));
+    pool_client.deposit(&user2, &(100 * STROOP as i128));
+
+    pool_client.borrow(&receiver, &(100 * STROOP as i128));
+    assert_eq!(pool_client.liabilities(), 800_000);
+
+    // 10% of what the pool owes is clawed back.
+    token_admin.clawback(&pool_addr, &200_080_000);
+    assert!(!pool_client.insolvent());
+
+    pool_client.update_fee_rewards(&user1);
+    assert!(pool_client.insolvent());
+
+    assert_eq!(
+        pool_client.try_deposit(&user1, &(STROOP as i128)),
+        Err(Ok(Error::from_contract_error(pool::Error::Insolvent as u32)))
+    );
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &(STROOP as i128)),
+        Err(Ok(Error::from_contract_error(pool::Error::Insolvent as u32)))
+    );
+
+    // only what was actually paid is reported.
+    assert_eq!(pool_client.withdraw_matured(&user1), 360_000);
+    assert_eq!(token.balance(&user1), 360_000);
+
+    assert_eq!(pool_client.withdraw(&user1, &(100 * STROOP as i128)), 90 * STROOP as i128);
+    assert_eq!(token.balance(&user1), (90 * STROOP as i128) + 360_000);
+
+    // the last liquidity provider takes the same haircut.
+    assert_eq!(pool_client.exit(&user2), (90 * STROOP as i128) + 360_000);
+    assert_eq!(token.balance(&user2), (90 * STROOP as i128) + 360_000);
+    assert_eq!(token.balance(&pool_addr), 0);
+}
+
+// Tests that a pool whose clawed back balance is topped back up leaves
+// insolvency mode and pays everyone in full again.
+#[test]
+fn insolvency_recovery() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin1 = Address::generate(&env);
+    let user1 = Address::generate(&env);
+
+    let sac = env.register_stellar_asset_contract_v2(admin1);
+    sac.issuer().set_flag(IssuerFlags::RevocableFlag);
+    sac.issuer().set_flag(IssuerFlags::ClawbackEnabledFlag);
+
+    let token_id = sac.address();
+    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+    let token = token::Client::new(&env, &token_id);
+
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    token_admin.mint(&user1, &(200 * STROOP as i128));
+    pool_client.deposit(&user1, &(100 * STROOP as i128));
+
+    token_admin.clawback(&pool_addr, &(10 * STROOP as i128));
+    pool_client.update_fee_rewards(&user1);
+    assert!(pool_client.insolvent());
+
+    // the clawed back funds are returned to the pool.
+    token_admin.mint(&pool_addr, &(10 * STROOP as i128));
+    pool_client.update_fee_rewards(&user1);
+    assert!(!pool_client.insolvent());
+
+    pool_client.deposit(&user1, &(100 * STROOP as i128));
+    assert_eq!(pool_client.exit(&user1), 200 * STROOP as i128);
+    assert_eq!(token.balance(&user1), 200 * STROOP as i128);
+}
+
+#[contract]
+pub struct FlashLoanReceiver;
+
+fn compute_fee(amount: &i128) -> i128 {
+    amount / 1250
+}
+
+#[contractimpl]
+impl FlashLoanReceiver {
+    pub fn init(e: Env, token: Address, fl_addr: Address) {
+        e.storage().instance().set(&symbol_short!("T"), &token);
+        e.storage().instance().set(&symbol_short!("FL"), &fl_addr);
+    }
+
+    pub fn exec_op(e: Env) {
+        let token_client = token::Client::new(
+            &e,
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("T"))
+                .unwrap(),
+        );
+
+        let total_amount = (100 * STROOP as i128) + compute_fee(&(100 * STROOP as i128));
+
+        token_client.approve(
+            &e.current_contract_address(),
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("FL"))
+                .unwrap(),
+            &total_amount,
+            &(e.ledger().sequence() + 1),
+        );
+    }
+}
@@ -26,7 +26,7 @@ fn collect_yield_raw() {
     let token_admin = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let pool_addr = env.register_contract_wasm(&None, pool::WASM);
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
     let pool_client = pool::Client::new(&env, &pool_addr);
 
     let receiver = env.register_contract(None, FlashLoanReceiver);
@@ -34,7 +34,6 @@ fn collect_yield_raw() {
 
     // Initialize the flash loan receiver contract.
     receiver_client.init(&user1, &token_id, &pool_addr);
-    pool_client.initialize(&token_id);
 
     token_admin.mint(&receiver, &(1000 * STROOP as i128));
     token_admin.mint(&user1, &(100 * STROOP as i128));
@@ -75,7 +74,7 @@ fn collect_yield_amounts() {
     let token_admin = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let pool_addr = env.register_contract_wasm(&None, pool::WASM);
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
     let pool_client = pool::Client::new(&env, &pool_addr);
 
     let receiver = env.register_contract(None, FlashLoanReceiver);
@@ -83,7 +82,6 @@ fn collect_yield_amounts() {
 
     // Initialize the flash loan receiver contract.
     receiver_client.init(&user1, &token_id, &pool_addr);
-    pool_client.initialize(&token_id);
 
     token_admin.mint(&receiver, &(1000 * STROOP as i128));
     token_admin.mint(&user1, &(100 * STROOP as i128));
@@ -138,7 +136,7 @@ fn yield_collect_sequence() {
     let token_admin = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let pool_addr = env.register_contract_wasm(&None, pool::WASM);
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
     let pool_client = pool::Client::new(&env, &pool_addr);
 
     let receiver = env.register_contract(None, FlashLoanReceiver);
@@ -146,7 +144,6 @@ fn yield_collect_sequence() {
 
     // Initialize the flash loan receiver contract.
     receiver_client.init(&user1, &token_id, &pool_addr);
-    pool_client.initialize(&token_id);
 
     token_admin.mint(&receiver, &(1000 * STROOP as i128));
     token_admin.mint(&user1, &(100 * STROOP as i128));
@@ -254,7 +251,7 @@ fn test_drain_pool() {
     let token_admin = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let pool_addr = env.register_contract_wasm(&None, pool::WASM);
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
     let pool_client = pool::Client::new(&env, &pool_addr);
 
     let receiver = env.register_contract(None, FlashLoanReceiver);
@@ -262,7 +259,6 @@ fn test_drain_pool() {
 
     // Initialize the flash loan receiver contract.
     receiver_client.init(&victim, &token_id, &pool_addr);
-    pool_client.initialize(&token_id);
 
     // Mint funds
     token_admin.mint(&receiver, &(1_000 * STROOP as i128));
@@ -21,11 +21,9 @@ fn deposit() {
     let token_admin = token::StellarAssetClient::new(&e, &token_id);
     let token = token::Client::new(&e, &token_id);
 
-    let pool_addr = e.register_contract_wasm(&None, pool::WASM); // 5;32
+    let pool_addr = e.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>)); // 5;32
     let pool_client = pool::Client::new(&e, &pool_addr);
 
-    pool_client.initialize(&token_id);
-
     token_admin.mint(&user1, &1000000000);
     token_admin.mint(&user2, &500000000);
     token_admin.mint(&user3, &500000000);
@@ -35,7 +33,6 @@ fn deposit() {
     assert_eq!(token.balance(&pool_addr), 1000000000);
 }
 
-
 #[should_panic(expected = "HostError: Error(Contract, #6)")]
 #[test]
 fn deposit_0() {
@@ -48,10 +45,39 @@ fn deposit_0() {
 
     let token_id = e.register_stellar_asset_contract(admin1);
 
-    let pool_addr = e.register_contract_wasm(&None, pool::WASM); // 5;32
+    let pool_addr = e.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>)); // 5;32
     let pool_client = pool::Client::new(&e, &pool_addr);
 
-    pool_client.initialize(&token_id);
-
     pool_client.deposit(&user1, &0);
 }
+
+// Tests that the shares of a deposit can be minted to a different address
+// than the one funding it, and that only the funder has to authorize.
+#[test]
+fn deposit_for() {
+    let e: Env = Default::default();
+    e.mock_all_auths();
+
+    let admin1 = Address::generate(&e);
+
+    let hot = Address::generate(&e);
+    let cold = Address::generate(&e);
+
+    let token_id = e.register_stellar_asset_contract(admin1);
+    let token_admin = token::StellarAssetClient::new(&e, &token_id);
+    let token = token::Client::new(&e, &token_id);
+
+    let pool_addr = e.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = pool::Client::new(&e, &pool_addr);
+
+    token_admin.mint(&hot, &1000000000);
+
+    pool_client.deposit_for(&hot, &cold, &1000000000);
+    assert_eq!(e.auths().len(), 1);
+    assert_eq!(e.auths()[0].0, hot);
+
+    assert_eq!(token.balance(&hot), 0);
+    assert_eq!(token.balance(&pool_addr), 1000000000);
+    assert_eq!(pool_client.shares(&hot), 0);
+    assert_eq!(pool_client.shares(&cold), 1000000000);
+}
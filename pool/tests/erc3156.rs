@@ -67,7 +67,7 @@ fn collect_yield_amounts_moderc3156() {
     let token_admin = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let pool_addr = env.register_contract_wasm(&None, pool::WASM);
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
     let pool_client = pool::Client::new(&env, &pool_addr);
 
     let receiver = env.register_contract(None, FlashLoanReceiverModifiedERC3156);
@@ -75,7 +75,6 @@ fn collect_yield_amounts_moderc3156() {
 
     // Initialize the flash loan receiver contract.
     receiver_client.init(&user1);
-    pool_client.initialize(&token_id);
 
     token_admin.mint(&receiver, &(1000 * STROOP as i128));
     token_admin.mint(&user1, &(100 * STROOP as i128));
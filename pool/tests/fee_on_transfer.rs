@@ -0,0 +1,164 @@
+mod pool {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+use fixed_point_math::STROOP;
+use soroban_sdk::{
+    contract, contractimpl, contracttype, symbol_short, testutils::Address as _, token, Address,
+    Env, Error, Symbol,
+};
+
+// Tests that deposits of a token which taxes transfers mint shares for
+// what the pool received, and that loans repaid short are rejected.
+#[test]
+fn fee_on_transfer() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let user1 = Address::generate(&env);
+
+    // 20% of every transfer is burned.
+    let token_id = env.register(TaxedToken, ());
+    let token_client = TaxedTokenClient::new(&env, &token_id);
+    token_client.set_tax(&2_000);
+
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    let receiver = env.register(FlashLoanReceiver, ());
+    let receiver_client = FlashLoanReceiverClient::new(&env, &receiver);
+    receiver_client.init(&token_id, &pool_addr);
+
+    token_client.mint(&user1, &(100 * STROOP as i128));
+    token_client.mint(&receiver, &(100 * STROOP as i128));
+
+    pool_client.deposit(&user1, &(100 * STROOP as i128));
+    assert_eq!(token_client.balance(&pool_addr), 80 * STROOP as i128);
+    assert_eq!(pool_client.shares(&user1), 80 * STROOP as i128);
+
+    // the repayment arrives 20% short.
+    assert_eq!(
+        pool_client.try_borrow(&receiver, &(50 * STROOP as i128)),
+        Err(Ok(Error::from_contract_error(pool::Error::LoanNotRepaid as u32)))
+    );
+
+    // without the tax the fee is collected as usual.
+    token_client.set_tax(&0);
+    pool_client.borrow(&receiver, &(50 * STROOP as i128));
+    assert_eq!(pool_client.liabilities(), 400_000);
+
+    // and everyone can be paid back.
+    pool_client.exit(&user1);
+    assert_eq!(token_client.balance(&user1), 80 * STROOP as i128 + 400_000);
+}
+
+#[contracttype]
+enum TaxedDataKey {
+    Tax,
+    Balance(Address),
+    Allowance(Address, Address),
+}
+
+#[contract]
+pub struct TaxedToken;
+
+fn read_balance(env: &Env, id: Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&TaxedDataKey::Balance(id))
+        .unwrap_or(0)
+}
+
+fn write_balance(env: &Env, id: Address, amount: i128) {
+    env.storage().persistent().set(&TaxedDataKey::Balance(id), &amount);
+}
+
+fn move_balance(env: &Env, from: Address, to: Address, amount: i128) {
+    let tax: i128 = env.storage().instance().get(&TaxedDataKey::Tax).unwrap_or(0);
+
+    write_balance(env, from.clone(), read_balance(env, from) - amount);
+    write_balance(env, to.clone(), read_balance(env, to) + amount - amount * tax / 10_000);
+}
+
+#[contractimpl]
+impl TaxedToken {
+    pub fn set_tax(env: Env, tax: i128) {
+        env.storage().instance().set(&TaxedDataKey::Tax, &tax);
+    }
+
+    pub fn mint(env: Env, to: Address, amount: i128) {
+        write_balance(&env, to.clone(), read_balance(&env, to) + amount);
+    }
+
+    pub fn balance(env: Env, id: Address) -> i128 {
+        read_balance(&env, id)
+    }
+
+    pub fn decimals(_env: Env) -> u32 {
+        7
+    }
+
+    pub fn approve(env: Env, from: Address, spender: Address, amount: i128, _expiration_ledger: u32) {
+        from.require_auth();
+        env.storage()
+            .temporary()
+            .set(&TaxedDataKey::Allowance(from, spender), &amount);
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        move_balance(&env, from, to, amount);
+    }
+
+    pub fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+
+        let key = TaxedDataKey::Allowance(from.clone(), spender);
+        let allowance: i128 = env.storage().temporary().get(&key).unwrap_or(0);
+        if allowance < amount {
+            panic!("insufficient allowance");
+        }
+
+        env.storage().temporary().set(&key, &(allowance - amount));
+        move_balance(&env, from, to, amount);
+    }
+}
+
+#[contract]
+pub struct FlashLoanReceiver;
+
+fn compute_fee(amount: &i128) -> i128 {
+    amount / 1250
+}
+
+#[contractimpl]
+impl FlashLoanReceiver {
+    pub fn init(e: Env, token: Address, fl_addr: Address) {
+        e.storage().instance().set(&symbol_short!("T"), &token);
+        e.storage().instance().set(&symbol_short!("FL"), &fl_addr);
+    }
+
+    pub fn exec_op(e: Env) {
+        let token_client = token::Client::new(
+            &e,
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("T"))
+                .unwrap(),
+        );
+
+        let total_amount = (50 * STROOP as i128) + compute_fee(&(50 * STROOP as i128));
+
+        token_client.approve(
+            &e.current_contract_address(),
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("FL"))
+                .unwrap(),
+            &total_amount,
+            &(e.ledger().sequence() + 1),
+        );
+    }
+}
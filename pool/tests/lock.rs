@@ -0,0 +1,236 @@
+mod pool {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+use fixed_point_math::STROOP;
+use soroban_sdk::{
+    contract, contractimpl, symbol_short,
+    testutils::{Address as _, Ledger},
+    token, Address, Env, Error, Symbol,
+};
+
+const DAY_IN_SECONDS: u64 = 86400;
+
+// Tests that locked shares earn a boosted share of the fees, can't
+// be withdrawn before expiry and stop being boosted once released.
+#[test]
+fn locked_deposit() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin1 = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(admin1);
+    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+    let token = token::Client::new(&env, &token_id);
+
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    FlashLoanReceiverClient::new(&env, &receiver).init(&token_id, &pool_addr);
+    token_admin.mint(&receiver, &(10 * STROOP as i128));
+
+    token_admin.mint(&user1, &(100 * STROOP as i128));
+    token_admin.mint(&user2, &(100 * STROOP as i128));
+
+    pool_client.deposit_locked(&user1, &(100 * STROOP as i128), &pool::LockTier::Days180);
+    pool_client.deposit(&user2, &(100 * STROOP as i128));
+
+    let lock = pool_client.lock(&user1).unwrap();
+    assert_eq!(lock.shares, 100 * STROOP as i128);
+    assert_eq!(lock.boost, pool_client.boost(&pool::LockTier::Days180));
+    assert_eq!(lock.unlock, env.ledger().timestamp() + 180 * DAY_IN_SECONDS);
+    assert_eq!(pool_client.effective_shares(&user1), 150 * STROOP as i128);
+    assert_eq!(pool_client.effective_shares(&user2), 100 * STROOP as i128);
+    assert_eq!(pool_client.lock(&user2), None);
+
+    // the 800_000 fee is split 150:100.
+    pool_client.borrow(&receiver, &(100 * STROOP as i128));
+    pool_client.update_fee_rewards(&user1);
+    pool_client.update_fee_rewards(&user2);
+    assert_eq!(pool_client.matured(&user1), 480_000);
+    assert_eq!(pool_client.matured(&user2), 320_000);
+
+    assert_eq!(
+        pool_client.try_withdraw(&user1, &(STROOP as i128)),
+        Err(Ok(Error::from_contract_error(pool::Error::SharesLocked as u32)))
+    );
+    assert_eq!(
+        pool_client.try_exit(&user1),
+        Err(Ok(Error::from_contract_error(pool::Error::SharesLocked as u32)))
+    );
+
+    // the lock can't be shortened.
+    token_admin.mint(&user1, &(10 * STROOP as i128));
+    assert_eq!(
+        pool_client.try_deposit_locked(&user1, &(10 * STROOP as i128), &pool::LockTier::Days30),
+        Err(Ok(Error::from_contract_error(pool::Error::InvalidLock as u32)))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp += 180 * DAY_IN_SECONDS);
+
+    // anyone can release an expired lock.
+    pool_client.update_fee_rewards(&user1);
+    assert_eq!(pool_client.lock(&user1), None);
+    assert_eq!(pool_client.effective_shares(&user1), 100 * STROOP as i128);
+
+    // unlocked shares behave like any other.
+    pool_client.borrow(&receiver, &(100 * STROOP as i128));
+    assert_eq!(pool_client.exit(&user1), (100 * STROOP as i128) + 480_000 + 400_000);
+    assert_eq!(token.balance(&user1), (110 * STROOP as i128) + 880_000);
+}
+
+// Tests that locks merge into the longer lock and that unlocked
+// shares of a liquidity provider with a lock stay withdrawable.
+#[test]
+fn merge_locks() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin1 = Address::generate(&env);
+    let user1 = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(admin1);
+    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    token_admin.mint(&user1, &(300 * STROOP as i128));
+
+    pool_client.deposit(&user1, &(100 * STROOP as i128));
+    pool_client.deposit_locked(&user1, &(100 * STROOP as i128), &pool::LockTier::Days30);
+    assert_eq!(pool_client.effective_shares(&user1), 210 * STROOP as i128);
+
+    env.ledger().with_mut(|li| li.timestamp += 10 * DAY_IN_SECONDS);
+    pool_client.deposit_locked(&user1, &(100 * STROOP as i128), &pool::LockTier::Days90);
+
+    let lock = pool_client.lock(&user1).unwrap();
+    assert_eq!(lock.shares, 200 * STROOP as i128);
+    assert_eq!(lock.tier, pool::LockTier::Days90);
+    assert_eq!(lock.unlock, env.ledger().timestamp() + 90 * DAY_IN_SECONDS);
+    assert_eq!(pool_client.effective_shares(&user1), 350 * STROOP as i128);
+
+    pool_client.withdraw(&user1, &(100 * STROOP as i128));
+    assert_eq!(
+        pool_client.try_withdraw(&user1, &(STROOP as i128)),
+        Err(Ok(Error::from_contract_error(pool::Error::SharesLocked as u32)))
+    );
+}
+
+// Tests that anyone can release a lock once it expires, after which
+// the fees aren't boosted anymore, and not a moment before.
+#[test]
+fn release_lock() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin1 = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(admin1);
+    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    FlashLoanReceiverClient::new(&env, &receiver).init(&token_id, &pool_addr);
+    token_admin.mint(&receiver, &(10 * STROOP as i128));
+
+    token_admin.mint(&user1, &(100 * STROOP as i128));
+    token_admin.mint(&user2, &(100 * STROOP as i128));
+
+    pool_client.deposit_locked(&user1, &(100 * STROOP as i128), &pool::LockTier::Days30);
+    pool_client.deposit(&user2, &(100 * STROOP as i128));
+
+    env.ledger().with_mut(|li| li.timestamp += 30 * DAY_IN_SECONDS - 1);
+    assert_eq!(
+        pool_client.try_release_lock(&user1),
+        Err(Ok(Error::from_contract_error(pool::Error::InvalidLock as u32)))
+    );
+    assert_eq!(
+        pool_client.try_release_lock(&user2),
+        Err(Ok(Error::from_contract_error(pool::Error::InvalidLock as u32)))
+    );
+
+    // released at expiry without the auth of the liquidity provider.
+    env.ledger().with_mut(|li| li.timestamp += 1);
+    pool_client.release_lock(&user1);
+    assert!(env.auths().is_empty());
+    assert_eq!(pool_client.lock(&user1), None);
+    assert_eq!(pool_client.effective_shares(&user1), 100 * STROOP as i128);
+
+    // the next fee is split evenly.
+    pool_client.borrow(&receiver, &(100 * STROOP as i128));
+    pool_client.update_fee_rewards(&user1);
+    pool_client.update_fee_rewards(&user2);
+    assert_eq!(pool_client.matured(&user1), 400_000);
+    assert_eq!(pool_client.matured(&user2), 400_000);
+}
+
+// Tests that a lock expiring past the largest timestamp is rejected.
+#[test]
+fn lock_overflow() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let user1 = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+    token::StellarAssetClient::new(&env, &token_id).mint(&user1, &(100 * STROOP as i128));
+
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    env.ledger().with_mut(|li| li.timestamp = u64::MAX - DAY_IN_SECONDS);
+    assert_eq!(
+        pool_client.try_deposit_locked(&user1, &(100 * STROOP as i128), &pool::LockTier::Days30),
+        Err(Ok(Error::from_contract_error(pool::Error::Overflow as u32)))
+    );
+}
+
+#[contract]
+pub struct FlashLoanReceiver;
+
+fn compute_fee(amount: &i128) -> i128 {
+    amount / 1250
+}
+
+#[contractimpl]
+impl FlashLoanReceiver {
+    pub fn init(e: Env, token: Address, fl_addr: Address) {
+        e.storage().instance().set(&symbol_short!("T"), &token);
+        e.storage().instance().set(&symbol_short!("FL"), &fl_addr);
+    }
+
+    pub fn exec_op(e: Env) {
+        let token_client = token::Client::new(
+            &e,
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("T"))
+                .unwrap(),
+        );
+
+        let total_amount = (100 * STROOP as i128) + compute_fee(&(100 * STROOP as i128));
+
+        token_client.approve(
+            &e.current_contract_address(),
+            &e.storage()
+                .instance()
+                .get::<Symbol, Address>(&symbol_short!("FL"))
+                .unwrap(),
+            &total_amount,
+            &(e.ledger().sequence() + 1),
+        );
+    }
+}
@@ -0,0 +1,170 @@
+mod pool {
+    use soroban_sdk::contractimport;
+    contractimport!(file = "../target/wasm32-unknown-unknown/release/xycloans_pool.wasm");
+}
+
+use fixed_point_math::STROOP;
+use inner_receiver::{InnerReceiver, InnerReceiverClient};
+use nested_receiver::{NestedReceiver, NestedReceiverClient};
+use soroban_sdk::{testutils::Address as _, token, Address, Env};
+
+// Receiver of a loan of 50 units from `outer` which borrows `amount` from `inner` meanwhile,
+// the outcome of the nested loan is recorded: 0 if it succeeded, the pool's error otherwise
+// and `u32::MAX` if the host rejected it.
+mod nested_receiver {
+    use super::{compute_fee, pool};
+    use fixed_point_math::STROOP;
+    use soroban_sdk::{contract, contractimpl, symbol_short, token, Address, Env, Symbol};
+
+    #[contract]
+    pub struct NestedReceiver;
+
+    #[contractimpl]
+    impl NestedReceiver {
+        pub fn init(env: Env, token: Address, outer: Address, inner: Address, inner_receiver: Address, amount: i128) {
+            env.storage().instance().set(&symbol_short!("T"), &token);
+            env.storage().instance().set(&symbol_short!("O"), &outer);
+            env.storage().instance().set(&symbol_short!("I"), &inner);
+            env.storage().instance().set(&symbol_short!("IR"), &inner_receiver);
+            env.storage().instance().set(&symbol_short!("A"), &amount);
+        }
+
+        pub fn exec_op(env: Env) {
+            let token: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("T")).unwrap();
+            let outer: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("O")).unwrap();
+            let inner: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("I")).unwrap();
+            let inner_receiver: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("IR")).unwrap();
+            let amount: i128 = env.storage().instance().get::<Symbol, i128>(&symbol_short!("A")).unwrap();
+
+            let code = match pool::Client::new(&env, &inner).try_borrow(&inner_receiver, &amount) {
+                Ok(Ok(())) => 0,
+                Err(Ok(error)) => error.get_code(),
+                _ => u32::MAX,
+            };
+            env.storage().instance().set(&symbol_short!("C"), &code);
+
+            token::Client::new(&env, &token).approve(
+                &env.current_contract_address(),
+                &outer,
+                &(50 * STROOP as i128 + compute_fee(&(50 * STROOP as i128))),
+                &(env.ledger().sequence() + 1),
+            );
+        }
+
+        pub fn code(env: Env) -> u32 {
+            env.storage().instance().get::<Symbol, u32>(&symbol_short!("C")).unwrap()
+        }
+    }
+}
+
+// Receiver of the nested loan, it repays it with fees.
+mod inner_receiver {
+    use super::compute_fee;
+    use soroban_sdk::{contract, contractimpl, symbol_short, token, Address, Env, Symbol};
+
+    #[contract]
+    pub struct InnerReceiver;
+
+    #[contractimpl]
+    impl InnerReceiver {
+        pub fn init(env: Env, token: Address, pool: Address, amount: i128) {
+            env.storage().instance().set(&symbol_short!("T"), &token);
+            env.storage().instance().set(&symbol_short!("P"), &pool);
+            env.storage().instance().set(&symbol_short!("A"), &amount);
+        }
+
+        pub fn exec_op(env: Env) {
+            let token: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("T")).unwrap();
+            let pool: Address = env.storage().instance().get::<Symbol, Address>(&symbol_short!("P")).unwrap();
+            let amount: i128 = env.storage().instance().get::<Symbol, i128>(&symbol_short!("A")).unwrap();
+
+            token::Client::new(&env, &token).approve(
+                &env.current_contract_address(),
+                &pool,
+                &(amount + compute_fee(&amount)),
+                &(env.ledger().sequence() + 1),
+            );
+        }
+    }
+}
+
+fn compute_fee(amount: &i128) -> i128 {
+    amount / 1250
+}
+
+// Deploys a pool of `token` with 100 units of liquidity.
+fn funded_pool(env: &Env, token: &Address) -> Address {
+    let lp = Address::generate(env);
+    let pool_addr = env.register(pool::WASM, (token.clone(), 8_u32, None::<Address>));
+
+    token::StellarAssetClient::new(env, token).mint(&lp, &(100 * STROOP as i128));
+    pool::Client::new(env, &pool_addr).deposit(&lp, &(100 * STROOP as i128));
+
+    pool_addr
+}
+
+// Sets up the receivers for a loan from `outer` during which 10 units
+// of `inner_token` are borrowed from `inner`.
+fn nested_receiver(env: &Env, token: &Address, outer: &Address, inner_token: &Address, inner: &Address) -> Address {
+    let amount = 10 * STROOP as i128;
+
+    let inner_receiver = env.register(InnerReceiver, ());
+    InnerReceiverClient::new(env, &inner_receiver).init(inner_token, inner, &amount);
+
+    let receiver = env.register(NestedReceiver, ());
+    NestedReceiverClient::new(env, &receiver).init(token, outer, inner, &inner_receiver, &amount);
+
+    // enough to pay the fees.
+    token::StellarAssetClient::new(env, token).mint(&receiver, &(STROOP as i128));
+    token::StellarAssetClient::new(env, inner_token).mint(&inner_receiver, &(STROOP as i128));
+
+    receiver
+}
+
+// Tests that a receiver can't borrow again from the pool it's borrowing from.
+#[test]
+fn nested_same_pool() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token_id = env.register_stellar_asset_contract(Address::generate(&env));
+    let pool_addr = funded_pool(&env, &token_id);
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    let receiver = nested_receiver(&env, &token_id, &pool_addr, &token_id, &pool_addr);
+    pool_client.borrow(&receiver, &(50 * STROOP as i128));
+
+    // the host rejects the receiver re-entering the pool before the pool's own `NestedLoan`
+    // guard is reached, either way only the outer loan paid a fee.
+    assert_ne!(NestedReceiverClient::new(&env, &receiver).code(), 0);
+    assert_eq!(pool_client.liabilities(), 400_000);
+    assert_eq!(token::Client::new(&env, &token_id).balance(&pool_addr), 100 * STROOP as i128 + 400_000);
+
+    // the pool lends again once the loan is repaid.
+    let other = nested_receiver(&env, &token_id, &pool_addr, &token_id, &pool_addr);
+    pool_client.borrow(&other, &(50 * STROOP as i128));
+    assert_eq!(pool_client.liabilities(), 800_000);
+}
+
+// Tests that a loan from another pool can be nested within a direct loan.
+#[test]
+fn nested_other_pool() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token0 = env.register_stellar_asset_contract(Address::generate(&env));
+    let token1 = env.register_stellar_asset_contract(Address::generate(&env));
+    let pool0 = funded_pool(&env, &token0);
+    let pool1 = funded_pool(&env, &token1);
+
+    let receiver = nested_receiver(&env, &token0, &pool0, &token1, &pool1);
+    pool::Client::new(&env, &pool0).borrow(&receiver, &(50 * STROOP as i128));
+
+    // both loans paid their fee.
+    assert_eq!(NestedReceiverClient::new(&env, &receiver).code(), 0);
+    assert_eq!(pool::Client::new(&env, &pool0).liabilities(), 400_000);
+    assert_eq!(pool::Client::new(&env, &pool1).liabilities(), 80_000);
+    assert_eq!(token::Client::new(&env, &token1).balance(&pool1), 100 * STROOP as i128 + 80_000);
+}
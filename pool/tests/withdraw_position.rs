@@ -10,7 +10,7 @@ mod pool {
 
 use fixed_point_math::STROOP;
 use soroban_sdk::{
-    contract, contractimpl, symbol_short, testutils::Address as _, token, Address, Env, Symbol,
+    contract, contractimpl, symbol_short, testutils::Address as _, token, Address, Env, Error, Symbol,
 };
 
 // Only tests the barebones liquidity withdrawal functionality.
@@ -27,12 +27,9 @@ fn withdraw_liquidity_raw() {
     let token_admin = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let pool_addr = env.register_contract_wasm(&None, pool::WASM);
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
     let pool_client = pool::Client::new(&env, &pool_addr);
 
-    // initialize the pool.
-    pool_client.initialize(&token_id);
-
     token_admin.mint(&user1, &(100 * STROOP as i128));
 
     // user1 deposits 50 TOKEN into the pool.
@@ -59,12 +56,9 @@ fn withdraw_liquidity_0() {
     let token_admin = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let pool_addr = env.register_contract_wasm(&None, pool::WASM);
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
     let pool_client = pool::Client::new(&env, &pool_addr);
 
-    // initialize the pool.
-    pool_client.initialize(&token_id);
-
     token_admin.mint(&user1, &(100 * STROOP as i128));
 
     // user1 deposits 50 TOKEN into the pool.
@@ -93,7 +87,7 @@ fn withdraw_liquidity_with_yield_raw() {
     let token_admin = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let pool_addr = env.register_contract_wasm(&None, pool::WASM);
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
     let pool_client = pool::Client::new(&env, &pool_addr);
 
     // Register, initialize and fund the receiver contract.
@@ -104,9 +98,6 @@ fn withdraw_liquidity_with_yield_raw() {
     receiver_client.init(&user1, &token_id, &pool_addr);
     token_admin.mint(&receiver, &(100 * STROOP as i128));
 
-    // initialize the pool.
-    pool_client.initialize(&token_id);
-
     token_admin.mint(&user1, &(100 * STROOP as i128));
 
     // user1 deposits 50 TOKEN into the pool.
@@ -148,7 +139,7 @@ fn yield_availability() {
     let token_admin = token::StellarAssetClient::new(&env, &token_id);
     let token = token::Client::new(&env, &token_id);
 
-    let pool_addr = env.register_contract_wasm(&None, pool::WASM);
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
     let pool_client = pool::Client::new(&env, &pool_addr);
 
     // Register, initialize and fund the receiver contract.
@@ -159,9 +150,6 @@ fn yield_availability() {
     receiver_client.init(&user1, &token_id, &pool_addr);
     token_admin.mint(&receiver, &(100 * STROOP as i128));
 
-    // initialize the pool.
-    pool_client.initialize(&token_id);
-
     token_admin.mint(&user1, &(100 * STROOP as i128));
     token_admin.mint(&user2, &(100 * STROOP as i128));
 
@@ -198,6 +186,99 @@ fn yield_availability() {
     pool_client.borrow(&receiver, &(50 * STROOP as i128));
 }
 
+// Tests that liquidity and matured yield can be paid out to a different
+// address than the liquidity provider, while only the provider's position changes.
+#[test]
+fn withdraw_to_recipient() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin1 = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(admin1);
+    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+    let token = token::Client::new(&env, &token_id);
+
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    let receiver_client = FlashLoanReceiverClient::new(&env, &receiver);
+    receiver_client.init(&user1, &token_id, &pool_addr);
+    token_admin.mint(&receiver, &(100 * STROOP as i128));
+
+    token_admin.mint(&user1, &(100 * STROOP as i128));
+    pool_client.deposit(&user1, &(100 * STROOP as i128));
+
+    pool_client.borrow(&receiver, &(50 * STROOP as i128));
+    pool_client.update_fee_rewards(&user1);
+
+    pool_client.withdraw_matured_to(&user1, &recipient);
+    assert_eq!(env.auths()[0].0, user1);
+    assert_eq!(token.balance(&recipient), 400_000);
+    assert_eq!(pool_client.matured(&user1), 0);
+
+    pool_client.withdraw_to(&user1, &recipient, &(40 * STROOP as i128));
+    assert_eq!(env.auths()[0].0, user1);
+    assert_eq!(token.balance(&recipient), (40 * STROOP as i128) + 400_000);
+    assert_eq!(token.balance(&user1), 0);
+    assert_eq!(pool_client.shares(&user1), (60 * STROOP as i128));
+    assert_eq!(pool_client.shares(&recipient), 0);
+}
+
+// Tests that exiting pays out the whole position together with the matured
+// yield in one call, and that positions without matured yield can exit too.
+#[test]
+fn exit() {
+    let env: Env = Default::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin1 = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+
+    let token_id = env.register_stellar_asset_contract(admin1);
+    let token_admin = token::StellarAssetClient::new(&env, &token_id);
+    let token = token::Client::new(&env, &token_id);
+
+    let pool_addr = env.register(pool::WASM, (token_id.clone(), 8_u32, None::<Address>));
+    let pool_client = pool::Client::new(&env, &pool_addr);
+
+    let receiver = env.register_contract(None, FlashLoanReceiver);
+    let receiver_client = FlashLoanReceiverClient::new(&env, &receiver);
+    receiver_client.init(&user1, &token_id, &pool_addr);
+    token_admin.mint(&receiver, &(100 * STROOP as i128));
+
+    token_admin.mint(&user1, &(100 * STROOP as i128));
+    pool_client.deposit(&user1, &(100 * STROOP as i128));
+
+    pool_client.borrow(&receiver, &(50 * STROOP as i128));
+
+    // user2 joins after the loan, so it has no matured yield.
+    token_admin.mint(&user2, &(50 * STROOP as i128));
+    pool_client.deposit(&user2, &(50 * STROOP as i128));
+
+    // rewards don't need to be updated before exiting.
+    assert_eq!(pool_client.exit(&user1), (100 * STROOP as i128) + 400_000);
+    assert_eq!(token.balance(&user1), (100 * STROOP as i128) + 400_000);
+    assert_eq!(pool_client.shares(&user1), 0);
+    assert_eq!(pool_client.matured(&user1), 0);
+
+    assert_eq!(pool_client.exit(&user2), (50 * STROOP as i128));
+    assert_eq!(token.balance(&user2), (50 * STROOP as i128));
+    assert_eq!(token.balance(&pool_addr), 0);
+
+    // there is nothing left to exit.
+    assert_eq!(
+        pool_client.try_exit(&user2),
+        Err(Ok(Error::from_contract_error(pool::Error::InvalidShareBalance as u32)))
+    );
+}
+
 #[contract]
 pub struct FlashLoanReceiver;
 